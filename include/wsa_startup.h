@@ -0,0 +1,49 @@
+// Generated by wsa-startup, do not edit
+#ifndef WSA_STARTUP_H
+#define WSA_STARTUP_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+// Acquires a handle keeping WSA initialized.
+//
+// WSA is initialized with version 2.2 if no other handle, from C or Rust, is alive.
+// Returns 0, or the error `WSAStartup` failed with.
+int32_t wsa_startup_acquire(void);
+
+// Acquires a handle keeping WSA initialized.
+//
+// WSA is initialized with version `major.minor` if no other handle, from C or Rust, is alive.
+// Returns 0, or the error `WSAStartup` failed with.
+int32_t wsa_startup_acquire_version(uint8_t major, uint8_t minor);
+
+// Releases a handle acquired through this API, cleaning WSA up if it was the last one.
+//
+// Returns 0, `WSANOTINITIALISED` (10093) if no handle acquired through this API is alive,
+// or the error `WSACleanup` failed with if WSA was started up through this API.
+int32_t wsa_startup_release(void);
+
+// The amount of handles, from C or Rust, currently keeping WSA initialized.
+size_t wsa_startup_refcount(void);
+
+// The error code the last acquire or release on this thread failed with, 0 if it succeeded.
+int32_t wsa_startup_last_error(void);
+
+// Writes the message of the last error on this thread into `buffer`, like `snprintf`.
+//
+// At most `size` bytes including the NUL terminator are written, and the length of
+// the whole message is returned. The message is empty if there is no last error.
+//
+// # Safety
+// `buffer` must be valid for writes of `size` bytes, it may only be NULL if `size` is 0.
+size_t wsa_startup_last_error_message(char *buffer, size_t size);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif // WSA_STARTUP_H
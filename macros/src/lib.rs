@@ -0,0 +1,84 @@
+//! This crate holds the attribute macros of `wsa-startup`, use them through its re-exports
+
+#![warn(clippy::pedantic, clippy::nursery, clippy::cargo)]
+
+mod options;
+
+use options::Options;
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, parse_quote, ItemFn};
+
+/// Initializes WSA before the body of `main` runs, and cleans it up after it returns or unwinds
+///
+/// Works on `async` functions as well, put it above or below the runtime's attribute
+/// (`#[tokio::main]`, `#[async_std::main]`, ...).
+///
+/// Options, all optional:
+/// - `version = "2.2"`, the version to initialize WSA with
+/// - `on_error = "panic" | "exit" | "return"`, what to do if the initialization fails:
+///   panic (the default), print the error and exit the process, or return it from `main`,
+///   which then has to return a `Result` whose error type implements `From<WsaError>`
+/// - `exit_code = 1`, the exit code to use with `on_error = "exit"`
+/// - `backend = expression`, the `Backend` to initialize with, the real `Winsock` by default
+#[proc_macro_attribute]
+pub fn main(args: TokenStream, item: TokenStream) -> TokenStream {
+    let mut options = Options::main();
+    let parser = syn::meta::parser(|meta| options.parse(&meta));
+    parse_macro_input!(args with parser);
+    let mut function = parse_macro_input!(item as ItemFn);
+
+    match options.initialize(&function.sig) {
+        Ok(initialize) => {
+            let body = &function.block;
+            function.block = parse_quote!({
+                #initialize
+                #body
+            });
+            quote!(#function).into()
+        }
+        Err(err) => err.into_compile_error().into(),
+    }
+}
+
+/// Initializes WSA around a test, and cleans it up after it returns or panics
+///
+/// Adds `#[test]` unless the function already has a test attribute,
+/// so `async` tests work above or below the runtime's attribute (`#[tokio::test]`, ...).
+///
+/// Takes the options of [`main`](macro@main), along with:
+/// - `simulated` or `simulated = name`, initializes with a fresh `Simulated` backend
+///   bound to `simulated` (or `name`) for the body to use
+/// - `fail_startup = [SystemNotReady, ...]`, `WsaError`s the simulated backend's next startups
+///   fail with, once the test's own initialization succeeded
+/// - `fail_cleanup = [NotInitialised, ...]`, `WsaError`s its next cleanups fail with
+#[proc_macro_attribute]
+pub fn test(args: TokenStream, item: TokenStream) -> TokenStream {
+    let mut options = Options::test();
+    let parser = syn::meta::parser(|meta| options.parse(&meta));
+    parse_macro_input!(args with parser);
+    let mut function = parse_macro_input!(item as ItemFn);
+
+    match options.initialize(&function.sig) {
+        Ok(initialize) => {
+            let body = &function.block;
+            function.block = parse_quote!({
+                #initialize
+                #body
+            });
+            let is_test = function.attrs.iter().any(|attr| {
+                attr.path()
+                    .segments
+                    .last()
+                    .is_some_and(|segment| segment.ident == "test")
+            });
+            if !is_test {
+                function
+                    .attrs
+                    .insert(0, parse_quote!(#[::core::prelude::v1::test]));
+            }
+            quote!(#function).into()
+        }
+        Err(err) => err.into_compile_error().into(),
+    }
+}
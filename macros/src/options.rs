@@ -0,0 +1,206 @@
+//! This module holds the options the attribute macros take, and the initialization they expand to
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{
+    meta::ParseNestedMeta, Error, Expr, ExprArray, Ident, LitInt, LitStr, Result, ReturnType,
+    Signature, Token,
+};
+
+/// What to do when initializing WSA fails
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum OnError {
+    #[default]
+    Panic,
+    Exit,
+    Return,
+}
+
+/// The options given to an attribute
+#[derive(Default)]
+pub struct Options {
+    test: bool,
+    version: Option<(u8, u8)>,
+    on_error: OnError,
+    exit_code: Option<LitInt>,
+    backend: Option<Expr>,
+    simulated: Option<Ident>,
+    fail_startup: Vec<Expr>,
+    fail_cleanup: Vec<Expr>,
+}
+
+impl Options {
+    /// The options of `#[wsa_startup::main]`
+    pub fn main() -> Self {
+        Self::default()
+    }
+
+    /// The options of `#[wsa_startup::test]`,
+    /// which also takes `simulated`, `fail_startup` and `fail_cleanup`
+    pub fn test() -> Self {
+        Self {
+            test: true,
+            ..Self::default()
+        }
+    }
+
+    /// Parses a single `name = value` option
+    pub fn parse(&mut self, meta: &ParseNestedMeta) -> Result<()> {
+        if self.test && meta.path.is_ident("simulated") {
+            self.simulated = Some(if meta.input.peek(Token![=]) {
+                meta.value()?.parse()?
+            } else {
+                Ident::new("simulated", meta.path.segments[0].ident.span())
+            });
+        } else if self.test && meta.path.is_ident("fail_startup") {
+            self.fail_startup = parse_errors(meta)?;
+        } else if self.test && meta.path.is_ident("fail_cleanup") {
+            self.fail_cleanup = parse_errors(meta)?;
+        } else if meta.path.is_ident("version") {
+            self.version = Some(parse_version(&meta.value()?.parse()?)?);
+        } else if meta.path.is_ident("on_error") {
+            let on_error: LitStr = meta.value()?.parse()?;
+            self.on_error = match on_error.value().as_str() {
+                "panic" => OnError::Panic,
+                "exit" => OnError::Exit,
+                "return" => OnError::Return,
+                _ => {
+                    return Err(Error::new(
+                        on_error.span(),
+                        "expected \"panic\", \"exit\" or \"return\"",
+                    ))
+                }
+            };
+        } else if meta.path.is_ident("exit_code") {
+            let exit_code: LitInt = meta.value()?.parse()?;
+            exit_code.base10_parse::<i32>()?;
+            self.exit_code = Some(exit_code);
+        } else if meta.path.is_ident("backend") {
+            self.backend = Some(meta.value()?.parse()?);
+        } else if self.test {
+            return Err(meta.error(
+                "unknown option, expected `version`, `on_error`, `exit_code`, `backend`, \
+                 `simulated`, `fail_startup` or `fail_cleanup`",
+            ));
+        } else {
+            return Err(meta.error(
+                "unknown option, expected `version`, `on_error`, `exit_code` or `backend`",
+            ));
+        }
+        Ok(())
+    }
+
+    /// The statement initializing WSA at the start of the function with the signature `sig`,
+    /// binding a guard that cleans it up when the function returns or unwinds
+    pub fn initialize(&self, sig: &Signature) -> Result<TokenStream> {
+        if let (Some(exit_code), false) = (&self.exit_code, self.on_error == OnError::Exit) {
+            return Err(Error::new(
+                exit_code.span(),
+                "`exit_code` only applies to `on_error = \"exit\"`",
+            ));
+        }
+        if self.on_error == OnError::Return && matches!(sig.output, ReturnType::Default) {
+            return Err(Error::new_spanned(
+                sig,
+                "`on_error = \"return\"` requires the function to return a `Result`",
+            ));
+        }
+
+        let (simulated_binding, backend) = match (&self.simulated, &self.backend) {
+            (Some(simulated), None) => (
+                Some(quote!(let #simulated = ::wsa_startup::Simulated::new();)),
+                quote!(::core::clone::Clone::clone(&#simulated)),
+            ),
+            (Some(simulated), Some(_)) => {
+                return Err(Error::new(
+                    simulated.span(),
+                    "`simulated` and `backend` can't be used together",
+                ))
+            }
+            (None, backend) => (
+                None,
+                backend.as_ref().map_or_else(
+                    || quote!(::wsa_startup::Winsock),
+                    |backend| quote!(#backend),
+                ),
+            ),
+        };
+        let inject = match &self.simulated {
+            Some(simulated) => {
+                let fail_startup = &self.fail_startup;
+                let fail_cleanup = &self.fail_cleanup;
+                quote! {
+                    #(#simulated.fail_startup(#fail_startup);)*
+                    #(#simulated.fail_cleanup(#fail_cleanup);)*
+                }
+            }
+            None => match self.fail_startup.iter().chain(&self.fail_cleanup).next() {
+                Some(failure) => {
+                    return Err(Error::new_spanned(
+                        failure,
+                        "injecting failures requires `simulated`",
+                    ))
+                }
+                None => quote!(),
+            },
+        };
+        let version = self.version.map(|(major, minor)| {
+            quote!(initializer.version(::wsa_startup::WsaVersion::new(#major, #minor));)
+        });
+        let on_error = match self.on_error {
+            OnError::Panic => quote!(::core::panic!("failed to initialize WSA: {}", err)),
+            OnError::Exit => {
+                let exit_code = self
+                    .exit_code
+                    .as_ref()
+                    .map_or_else(|| quote!(1), |exit_code| quote!(#exit_code));
+                quote!({
+                    ::std::eprintln!("failed to initialize WSA: {}", err);
+                    ::std::process::exit(#exit_code)
+                })
+            }
+            OnError::Return => {
+                quote!(return ::core::result::Result::Err(::core::convert::From::from(err)))
+            }
+        };
+        let guard = Ident::new("_wsa_startup_guard", Span::mixed_site());
+
+        Ok(quote! {
+            #simulated_binding
+            let #guard = {
+                let mut initializer = ::wsa_startup::WsaInitializer::with_backend(#backend);
+                #version
+                match initializer.init() {
+                    ::core::result::Result::Ok(wsa) => wsa.raii(),
+                    ::core::result::Result::Err(err) => #on_error,
+                }
+            };
+            #inject
+        })
+    }
+}
+
+/// Parses a list of errors to inject, `[SystemNotReady, WsaError::Other(42)]`,
+/// bare names are taken from `WsaError`
+fn parse_errors(meta: &ParseNestedMeta) -> Result<Vec<Expr>> {
+    let errors: ExprArray = meta.value()?.parse()?;
+    Ok(errors
+        .elems
+        .into_iter()
+        .map(|err| match err {
+            Expr::Path(path) if path.path.get_ident().is_some() => {
+                syn::parse_quote!(::wsa_startup::WsaError::#path)
+            }
+            err => err,
+        })
+        .collect())
+}
+
+/// Parses a `"major.minor"` version, so a typo is caught at compile time
+fn parse_version(version: &LitStr) -> Result<(u8, u8)> {
+    version
+        .value()
+        .split_once('.')
+        .and_then(|(major, minor)| Some((major.parse().ok()?, minor.parse().ok()?)))
+        .ok_or_else(|| Error::new(version.span(), "expected a version such as \"2.2\""))
+}
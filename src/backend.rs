@@ -0,0 +1,301 @@
+//! This module holds the [`Backend`] trait, which abstracts the Winsock calls this crate makes,
+//! along with the real [`Winsock`] backend and an in-memory [`Simulated`] one
+
+use crate::{protocol, sys, ProtocolInfo, Result, WsaError, WsaInfo, WsaVersion};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+/// The Winsock calls needed to start up and clean up WSA
+pub trait Backend {
+    /// Calls `WSAStartup` asking for `version`
+    /// # Errors
+    /// Returns the [`WsaError`] `WSAStartup` failed with
+    fn startup(&self, version: WsaVersion) -> Result<WsaInfo>;
+
+    /// Calls `WSACleanup`
+    /// # Errors
+    /// Returns the [`WsaError`] `WSACleanup` failed with
+    fn cleanup(&self) -> Result<()>;
+
+    /// Calls `WSAGetLastError`, [`None`] if there is no error
+    fn last_error(&self) -> Option<WsaError>;
+
+    /// Calls `WSAEnumProtocolsW` for every protocol installed in the catalog
+    /// # Errors
+    /// Returns the [`WsaError`] `WSAEnumProtocolsW` failed with,
+    /// or [`WsaError::OperationNotSupported`] if the backend can't enumerate protocols
+    fn protocols(&self) -> Result<Vec<ProtocolInfo>> {
+        Err(WsaError::OperationNotSupported)
+    }
+}
+
+/// The real Winsock, as linked from `ws2_32`
+///
+/// Built with `dynamic` but neither `winapi` nor `windows-sys`, `ws2_32` is loaded on first use instead,
+/// and startups fail with [`WsaError::SystemNotReady`] if it is missing, `Winsock::load_error` tells why
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Winsock;
+
+impl Winsock {
+    /// Why `ws2_32` couldn't be loaded at runtime, trying to load it if it wasn't yet.\
+    /// Always [`None`] when it is linked through `winapi` or `windows-sys`, or off windows
+    #[cfg(feature = "dynamic")]
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)]
+    pub fn load_error() -> Option<&'static crate::LoadError> {
+        #[cfg(all(windows, not(any(feature = "winapi", feature = "windows-sys"))))]
+        let err = crate::dynamic::process::load_error();
+        #[cfg(not(all(windows, not(any(feature = "winapi", feature = "windows-sys")))))]
+        let err = None;
+        err
+    }
+}
+
+impl Backend for Winsock {
+    fn startup(&self, version: WsaVersion) -> Result<WsaInfo> {
+        let mut data: sys::WSADATA = unsafe { std::mem::zeroed() };
+        // WSAStartup(u16, *mut WSADATA) -> i32, reminder: UnsafeCell
+        let result = unsafe { sys::WSAStartup(version.to_word(), &raw mut data) };
+        if result == 0 {
+            Ok(WsaInfo::from(&data))
+        } else {
+            Err(result.into())
+        }
+    }
+
+    fn cleanup(&self) -> Result<()> {
+        crate::check(unsafe { sys::WSACleanup() }).map(drop)
+    }
+
+    fn last_error(&self) -> Option<WsaError> {
+        crate::last_error()
+    }
+
+    fn protocols(&self) -> Result<Vec<ProtocolInfo>> {
+        protocol::enumerate(
+            |buffer, len| unsafe {
+                sys::WSAEnumProtocolsW(std::ptr::null_mut(), buffer.cast(), len)
+            },
+            crate::last_error,
+        )
+    }
+}
+
+/// An in-memory Winsock, for running code on any platform and injecting failures into it.\
+/// Clones share the same state, like every part of a process shares the same Winsock
+#[derive(Debug, Clone, Default)]
+pub struct Simulated(Arc<Mutex<State>>);
+
+#[derive(Debug)]
+struct State {
+    startups: usize,
+    low_version: WsaVersion,
+    high_version: WsaVersion,
+    startup_failures: VecDeque<WsaError>,
+    cleanup_failures: VecDeque<WsaError>,
+    last_error: Option<WsaError>,
+    protocols: Vec<ProtocolInfo>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            startups: 0,
+            low_version: WsaVersion::new(1, 0),
+            high_version: WsaVersion::V2_2,
+            startup_failures: VecDeque::new(),
+            cleanup_failures: VecDeque::new(),
+            last_error: None,
+            protocols: Vec::new(),
+        }
+    }
+}
+
+impl Simulated {
+    /// A simulated Winsock supporting versions 1.0 through 2.2, like every supported windows does
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A simulated Winsock supporting versions `low` through `high`
+    #[must_use]
+    pub fn supporting(low: WsaVersion, high: WsaVersion) -> Self {
+        let simulated = Self::default();
+        {
+            let mut state = simulated.state();
+            state.low_version = low;
+            state.high_version = high;
+        }
+        simulated
+    }
+
+    /// Makes the next `startup` that isn't already set to fail, fail with `err`
+    #[allow(clippy::must_use_candidate)]
+    pub fn fail_startup(&self, err: WsaError) -> &Self {
+        self.state().startup_failures.push_back(err);
+        self
+    }
+
+    /// Makes the next `cleanup` that isn't already set to fail, fail with `err`
+    #[allow(clippy::must_use_candidate)]
+    pub fn fail_cleanup(&self, err: WsaError) -> &Self {
+        self.state().cleanup_failures.push_back(err);
+        self
+    }
+
+    /// Makes `protocols` report `catalog`, while WSA is started up
+    #[allow(clippy::must_use_candidate)]
+    pub fn set_protocols(&self, catalog: Vec<ProtocolInfo>) -> &Self {
+        self.state().protocols = catalog;
+        self
+    }
+
+    /// How many successful startups weren't cleaned up yet
+    #[must_use]
+    pub fn startups(&self) -> usize {
+        self.state().startups
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl State {
+    const fn fail(&mut self, err: WsaError) -> Result<()> {
+        self.last_error = Some(err);
+        Err(err)
+    }
+}
+
+impl Backend for Simulated {
+    fn startup(&self, version: WsaVersion) -> Result<WsaInfo> {
+        let mut state = self.state();
+        if let Some(err) = state.startup_failures.pop_front() {
+            state.fail(err)?;
+        }
+        if version < state.low_version {
+            state.fail(WsaError::VersionNotSupported)?;
+        }
+        state.startups += 1;
+        let negotiated = version.min(state.high_version);
+        Ok(WsaInfo::new(
+            negotiated,
+            state.high_version,
+            "Simulated WinSock",
+            "Running",
+        ))
+    }
+
+    fn cleanup(&self) -> Result<()> {
+        let mut state = self.state();
+        if let Some(err) = state.cleanup_failures.pop_front() {
+            return state.fail(err);
+        }
+        match state.startups.checked_sub(1) {
+            Some(startups) => {
+                state.startups = startups;
+                Ok(())
+            }
+            None => state.fail(WsaError::NotInitialised),
+        }
+    }
+
+    fn last_error(&self) -> Option<WsaError> {
+        self.state().last_error
+    }
+
+    fn protocols(&self) -> Result<Vec<ProtocolInfo>> {
+        let mut state = self.state();
+        if state.startups == 0 {
+            state.fail(WsaError::NotInitialised)?;
+        }
+        Ok(state.protocols.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Backend, Simulated};
+    #[cfg(not(windows))]
+    use crate::{sys, Winsock};
+    use crate::{WsaError, WsaVersion};
+
+    #[test]
+    fn counts_startups() {
+        let simulated = Simulated::new();
+        let other = simulated.clone();
+        simulated.startup(WsaVersion::V2_2).unwrap();
+        other.startup(WsaVersion::V2_2).unwrap();
+        assert_eq!(simulated.startups(), 2);
+
+        simulated.cleanup().unwrap();
+        other.cleanup().unwrap();
+        assert_eq!(other.startups(), 0);
+        assert_eq!(simulated.last_error(), None);
+    }
+
+    #[test]
+    fn unbalanced_cleanup() {
+        let simulated = Simulated::new();
+        assert_eq!(simulated.cleanup(), Err(WsaError::NotInitialised));
+        assert_eq!(simulated.last_error(), Some(WsaError::NotInitialised));
+        assert_eq!(simulated.startups(), 0);
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn unbalanced_noop_cleanup() {
+        let _serial = sys::serial();
+        assert_eq!(Winsock.cleanup(), Err(WsaError::NotInitialised));
+        assert_eq!(Winsock.last_error(), Some(WsaError::NotInitialised));
+        Winsock.startup(WsaVersion::V2_2).unwrap();
+        assert_eq!(Winsock.cleanup(), Ok(()));
+        assert_eq!(sys::startups(), 0);
+    }
+
+    #[test]
+    fn negotiates_versions() {
+        let simulated = Simulated::supporting(WsaVersion::new(1, 1), WsaVersion::new(2, 0));
+        let info = simulated.startup(WsaVersion::V2_2).unwrap();
+        assert_eq!(info.version(), WsaVersion::new(2, 0));
+        assert_eq!(info.high_version(), WsaVersion::new(2, 0));
+
+        let info = simulated.startup(WsaVersion::new(1, 1)).unwrap();
+        assert_eq!(info.version(), WsaVersion::new(1, 1));
+
+        assert_eq!(
+            simulated.startup(WsaVersion::new(1, 0)),
+            Err(WsaError::VersionNotSupported)
+        );
+        assert_eq!(simulated.startups(), 2);
+    }
+
+    #[test]
+    fn injected_failures_in_order() {
+        let simulated = Simulated::new();
+        simulated
+            .fail_startup(WsaError::SystemNotReady)
+            .fail_startup(WsaError::TasksLimitReached)
+            .fail_cleanup(WsaError::OperationInProgress);
+
+        assert_eq!(
+            simulated.startup(WsaVersion::V2_2),
+            Err(WsaError::SystemNotReady)
+        );
+        assert_eq!(
+            simulated.startup(WsaVersion::V2_2),
+            Err(WsaError::TasksLimitReached)
+        );
+        assert_eq!(simulated.last_error(), Some(WsaError::TasksLimitReached));
+        assert!(simulated.startup(WsaVersion::V2_2).is_ok());
+
+        assert_eq!(simulated.cleanup(), Err(WsaError::OperationInProgress));
+        assert_eq!(simulated.startups(), 1);
+        assert_eq!(simulated.cleanup(), Ok(()));
+        assert_eq!(simulated.startups(), 0);
+    }
+}
@@ -0,0 +1,277 @@
+//! This module exposes the [`SharedWsa`] reference count to C and C++, so components written in
+//! other languages can share the same WSA initialization instead of calling `WSAStartup` on their own.
+//!
+//! Enabling the feature only adds the exports to the Rust library, build the C library with
+//! `cargo rustc --release --features capi --crate-type cdylib` (or `staticlib`)
+//! and include the header returned by [`header`], which is checked in as `include/wsa_startup.h`.
+//!
+//! Acquiring and releasing return `0` on success or the Winsock error code they failed with,
+//! which is also kept as the calling thread's last error until its next acquire or release.
+//!
+//! WSA is started up through the real [`Winsock`] unless [`set_backend`] swapped it,
+//! for example for a [`Simulated`](crate::Simulated) one to test the C side against.
+
+use crate::{
+    Backend, CleanupPolicy, ProtocolInfo, Result, SharedWsa, Winsock, WsaError, WsaInfo,
+    WsaInitializer, WsaVersion,
+};
+use std::{
+    cell::Cell,
+    fmt::Write,
+    os::raw::c_char,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+/// The handles acquired through the C API, released one at a time
+static HANDLES: Mutex<Vec<SharedWsa>> = Mutex::new(Vec::new());
+
+/// The backend set by [`set_backend`], [`Winsock`] if there is none
+static BACKEND: Mutex<Option<Arc<dyn Backend + Send + Sync>>> = Mutex::new(None);
+
+thread_local! {
+    /// The error of the last C API call made by this thread
+    static LAST_ERROR: Cell<Option<WsaError>> = const { Cell::new(None) };
+    /// The error `WSACleanup` failed with when this thread dropped the last handle
+    static CLEANUP: Cell<Option<WsaError>> = const { Cell::new(None) };
+}
+
+fn handles() -> MutexGuard<'static, Vec<SharedWsa>> {
+    HANDLES.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Makes the C API start WSA up through `backend` from now on.\
+/// Handles already alive keep cleaning up through the backend WSA was started up with
+pub fn set_backend(backend: impl Backend + Send + Sync + 'static) {
+    *BACKEND.lock().unwrap_or_else(PoisonError::into_inner) = Some(Arc::new(backend));
+}
+
+/// The backend the C API starts WSA up through
+#[derive(Clone)]
+struct CBackend(Arc<dyn Backend + Send + Sync>);
+
+impl Backend for CBackend {
+    fn startup(&self, version: WsaVersion) -> Result<WsaInfo> {
+        self.0.startup(version)
+    }
+
+    fn cleanup(&self) -> Result<()> {
+        self.0.cleanup()
+    }
+
+    fn last_error(&self) -> Option<WsaError> {
+        self.0.last_error()
+    }
+
+    fn protocols(&self) -> Result<Vec<ProtocolInfo>> {
+        self.0.protocols()
+    }
+}
+
+/// An initializer for version `major.minor`, reporting cleanup failures to the thread releasing the last handle
+fn initializer(major: u8, minor: u8) -> WsaInitializer<CBackend> {
+    let backend = BACKEND
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+        .unwrap_or_else(|| Arc::new(Winsock));
+    let mut initializer = WsaInitializer::with_backend(CBackend(backend));
+    initializer
+        .version((major, minor))
+        .cleanup_policy(CleanupPolicy::callback(|err| {
+            CLEANUP.with(|cleanup| cleanup.set(Some(err)));
+        }));
+    initializer
+}
+
+/// Records the outcome of a C API call as this thread's last error, and turns it into its code
+fn report(result: Result<()>) -> i32 {
+    let error = result.err();
+    LAST_ERROR.with(|last| last.set(error));
+    error.map_or(0, WsaError::code)
+}
+
+/// The name of a Rust type in C
+trait CType {
+    const NAME: &'static str;
+}
+
+impl CType for i32 {
+    const NAME: &'static str = "int32_t";
+}
+
+impl CType for u8 {
+    const NAME: &'static str = "uint8_t";
+}
+
+impl CType for usize {
+    const NAME: &'static str = "size_t";
+}
+
+impl CType for *mut c_char {
+    const NAME: &'static str = "char *";
+}
+
+/// The declaration of an exported function in the header
+struct Declaration {
+    docs: &'static [&'static str],
+    ret: &'static str,
+    name: &'static str,
+    args: &'static [(&'static str, &'static str)],
+}
+
+/// Defines the exported functions, collecting their declarations for the header into `DECLARATIONS`
+macro_rules! exports {
+    (@munch [$($declarations:expr,)*]) => {
+        const DECLARATIONS: &[Declaration] = &[$($declarations,)*];
+    };
+    (@munch [$($declarations:expr,)*] $(#[doc = $doc:literal])* unsafe fn $($rest:tt)*) => {
+        exports!(@item [$($declarations,)*] [$($doc)*] [unsafe] $($rest)*);
+    };
+    (@munch [$($declarations:expr,)*] $(#[doc = $doc:literal])* fn $($rest:tt)*) => {
+        exports!(@item [$($declarations,)*] [$($doc)*] [] $($rest)*);
+    };
+    (
+        @item [$($declarations:expr,)*] [$($doc:literal)*] [$($unsafe:tt)?]
+        $name:ident($($arg:ident: $ty:ty),*) -> $ret:ty $body:block $($rest:tt)*
+    ) => {
+        $(#[doc = $doc])*
+        #[no_mangle]
+        pub $($unsafe)? extern "C" fn $name($($arg: $ty),*) -> $ret $body
+
+        exports!(@munch [$($declarations,)* Declaration {
+            docs: &[$($doc),*],
+            ret: <$ret as CType>::NAME,
+            name: stringify!($name),
+            args: &[$((<$ty as CType>::NAME, stringify!($arg))),*],
+        },] $($rest)*);
+    };
+    ($($items:tt)*) => {
+        exports!(@munch [] $($items)*);
+    };
+}
+
+exports! {
+    /// Acquires a handle keeping WSA initialized.
+    ///
+    /// WSA is initialized with version 2.2 if no other handle, from C or Rust, is alive.
+    /// Returns 0, or the error `WSAStartup` failed with.
+    fn wsa_startup_acquire() -> i32 {
+        report(initializer(2, 2).shared().map(|handle| handles().push(handle)))
+    }
+
+    /// Acquires a handle keeping WSA initialized.
+    ///
+    /// WSA is initialized with version `major.minor` if no other handle, from C or Rust, is alive.
+    /// Returns 0, or the error `WSAStartup` failed with.
+    fn wsa_startup_acquire_version(major: u8, minor: u8) -> i32 {
+        report(initializer(major, minor).shared().map(|handle| handles().push(handle)))
+    }
+
+    /// Releases a handle acquired through this API, cleaning WSA up if it was the last one.
+    ///
+    /// Returns 0, `WSANOTINITIALISED` (10093) if no handle acquired through this API is alive,
+    /// or the error `WSACleanup` failed with if WSA was started up through this API.
+    fn wsa_startup_release() -> i32 {
+        let handle = handles().pop();
+        report(handle.ok_or(WsaError::NotInitialised).and_then(|handle| {
+            CLEANUP.with(|cleanup| cleanup.set(None));
+            drop(handle);
+            CLEANUP.with(Cell::take).map_or(Ok(()), Err)
+        }))
+    }
+
+    /// The amount of handles, from C or Rust, currently keeping WSA initialized.
+    fn wsa_startup_refcount() -> usize {
+        SharedWsa::count()
+    }
+
+    /// The error code the last acquire or release on this thread failed with, 0 if it succeeded.
+    fn wsa_startup_last_error() -> i32 {
+        LAST_ERROR.with(Cell::get).map_or(0, WsaError::code)
+    }
+
+    /// Writes the message of the last error on this thread into `buffer`, like `snprintf`.
+    ///
+    /// At most `size` bytes including the NUL terminator are written, and the length of
+    /// the whole message is returned. The message is empty if there is no last error.
+    ///
+    /// # Safety
+    /// `buffer` must be valid for writes of `size` bytes, it may only be NULL if `size` is 0.
+    unsafe fn wsa_startup_last_error_message(buffer: *mut c_char, size: usize) -> usize {
+        let message = LAST_ERROR
+            .with(Cell::get)
+            .map(|error| error.to_string())
+            .unwrap_or_default();
+        if size > 0 {
+            let written = message.len().min(size - 1);
+            // The caller guarantees the buffer holds size bytes, and written is less than that
+            unsafe {
+                std::ptr::copy_nonoverlapping(message.as_ptr().cast(), buffer, written);
+                *buffer.add(written) = 0;
+            }
+        }
+        message.len()
+    }
+}
+
+/// The C header declaring the exported functions
+#[must_use]
+pub fn header() -> String {
+    let mut header = String::from(
+        "// Generated by wsa-startup, do not edit\n\
+         #ifndef WSA_STARTUP_H\n\
+         #define WSA_STARTUP_H\n\
+         \n\
+         #include <stddef.h>\n\
+         #include <stdint.h>\n\
+         \n\
+         #ifdef __cplusplus\n\
+         extern \"C\" {\n\
+         #endif\n",
+    );
+    for declaration in DECLARATIONS {
+        header.push('\n');
+        for doc in declaration.docs {
+            let _ = writeln!(header, "//{doc}");
+        }
+        let args: Vec<_> = declaration
+            .args
+            .iter()
+            .map(|(ty, arg)| {
+                // Pointers stick to the name they declare
+                let space = if ty.ends_with('*') { "" } else { " " };
+                format!("{ty}{space}{arg}")
+            })
+            .collect();
+        let args = if args.is_empty() {
+            "void".to_owned()
+        } else {
+            args.join(", ")
+        };
+        let _ = writeln!(header, "{} {}({args});", declaration.ret, declaration.name);
+    }
+    header.push_str(
+        "\n\
+         #ifdef __cplusplus\n\
+         }\n\
+         #endif\n\
+         \n\
+         #endif // WSA_STARTUP_H\n",
+    );
+    header
+}
+
+#[cfg(test)]
+mod tests {
+    use super::header;
+    use crate::golden::golden;
+
+    #[test]
+    fn header_is_up_to_date() {
+        golden(
+            "include/wsa_startup.h",
+            include_str!("../include/wsa_startup.h"),
+            &header(),
+        );
+    }
+}
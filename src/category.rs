@@ -0,0 +1,463 @@
+//! This module classifies [`WsaError`]s, to decide what to do about them without matching on every code
+
+use crate::WsaError;
+
+/// What kind of failure a [`WsaError`] describes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCategory {
+    /// The operation hasn't completed yet, or would have to block
+    Pending,
+    /// The operation was interrupted or cancelled
+    Cancelled,
+    /// A limit on memory, handles, tasks or quotas was reached
+    Resources,
+    /// Access was denied
+    Permission,
+    /// Winsock or one of its service providers is missing, broken or of the wrong version
+    Configuration,
+    /// The protocol, address family or operation isn't supported
+    Unsupported,
+    /// The call was made incorrectly, such as with an invalid argument or in the wrong state
+    Usage,
+    /// The network stack, a network or a host is down, unreachable, or dropped the connection
+    Network,
+    /// A name, service or record couldn't be resolved
+    NameResolution,
+    /// A Quality of Service failure or status
+    Qos,
+    /// A code that isn't part of the catalogue
+    Unknown,
+}
+
+impl WsaError {
+    /// What kind of failure this is
+    #[must_use]
+    pub const fn category(self) -> ErrorCategory {
+        match self {
+            Self::IoIncomplete
+            | Self::IoPending
+            | Self::WouldBlock
+            | Self::OperationInProgress
+            | Self::AlreadyInProgress => ErrorCategory::Pending,
+            Self::Interrupted
+            | Self::OperationAborted
+            | Self::Cancelled
+            | Self::LookupCancelled => ErrorCategory::Cancelled,
+            Self::NotEnoughMemory
+            | Self::TooManyOpenFiles
+            | Self::NoBufferSpace
+            | Self::TooManyReferences
+            | Self::TasksLimitReached
+            | Self::UserQuotaExceeded
+            | Self::DiskQuotaExceeded => ErrorCategory::Resources,
+            Self::PermissionDenied | Self::QueryRefused => ErrorCategory::Permission,
+            Self::VersionNotSupported
+            | Self::InvalidProcedureTable
+            | Self::InvalidProvider
+            | Self::ProviderFailedInit
+            | Self::SystemCallFailure => ErrorCategory::Configuration,
+            Self::ProtocolNotSupported
+            | Self::SocketTypeNotSupported
+            | Self::OperationNotSupported
+            | Self::ProtocolFamilyNotSupported
+            | Self::AddressFamilyNotSupported => ErrorCategory::Unsupported,
+            Self::InvalidHandle
+            | Self::InvalidParameter
+            | Self::BadFileHandle
+            | Self::InvalidData
+            | Self::InvalidArgument
+            | Self::NotASocket
+            | Self::DestinationAddressRequired
+            | Self::MessageTooLong
+            | Self::WrongProtocolType
+            | Self::BadProtocolOption
+            | Self::AlreadyConnected
+            | Self::NotConnected
+            | Self::Shutdown
+            | Self::CannotTranslateName
+            | Self::NameTooLong
+            | Self::DirectoryNotEmpty
+            | Self::StaleHandle
+            | Self::ItemIsRemote
+            | Self::NotInitialised => ErrorCategory::Usage,
+            Self::AddressInUse
+            | Self::AddressNotAvailable
+            | Self::NetworkDown
+            | Self::NetworkUnreachable
+            | Self::NetworkReset
+            | Self::ConnectionAborted
+            | Self::ConnectionReset
+            | Self::TimedOut
+            | Self::ConnectionRefused
+            | Self::HostDown
+            | Self::HostUnreachable
+            | Self::SystemNotReady
+            | Self::GracefulShutdown => ErrorCategory::Network,
+            Self::NoMore
+            | Self::ServiceNotFound
+            | Self::TypeNotFound
+            | Self::NoMoreResults
+            | Self::HostNotFound
+            | Self::TryAgain
+            | Self::NoRecovery
+            | Self::NoData => ErrorCategory::NameResolution,
+            Self::QosReceivers
+            | Self::QosSenders
+            | Self::QosNoSenders
+            | Self::QosNoReceivers
+            | Self::QosRequestConfirmed
+            | Self::QosAdmissionFailure
+            | Self::QosPolicyFailure
+            | Self::QosBadStyle
+            | Self::QosBadObject
+            | Self::QosTrafficControlError
+            | Self::QosGenericError
+            | Self::QosServiceTypeError
+            | Self::QosFlowspecError
+            | Self::QosInvalidProviderBuffer
+            | Self::QosInvalidFilterStyle
+            | Self::QosInvalidFilterType
+            | Self::QosIncorrectFilterCount
+            | Self::QosInvalidObjectLength
+            | Self::QosIncorrectFlowCount
+            | Self::QosUnknownObject
+            | Self::QosInvalidPolicyObject
+            | Self::QosInvalidFlowDescriptor
+            | Self::QosInvalidProviderFlowspec
+            | Self::QosInvalidProviderFilterspec
+            | Self::QosInvalidShapeDiscardMode
+            | Self::QosInvalidShapingRate
+            | Self::QosReservedPolicyElement => ErrorCategory::Qos,
+            Self::Other(_) => ErrorCategory::Unknown,
+        }
+    }
+
+    /// Whether the error may go away by itself, so the failed call is worth retrying,
+    /// such as [`SystemNotReady`](Self::SystemNotReady) right after boot
+    #[must_use]
+    pub const fn is_transient(self) -> bool {
+        match self.category() {
+            // Overlapped operations are still running, calling again won't complete them
+            ErrorCategory::Pending => !matches!(self, Self::IoIncomplete | Self::IoPending),
+            // Resources that are freed as others finish, unlike handles and quotas held by the caller
+            ErrorCategory::Resources => !matches!(
+                self,
+                Self::TooManyOpenFiles
+                    | Self::TooManyReferences
+                    | Self::UserQuotaExceeded
+                    | Self::DiskQuotaExceeded
+            ),
+            ErrorCategory::Cancelled => matches!(self, Self::Interrupted),
+            ErrorCategory::Network => {
+                matches!(
+                    self,
+                    Self::NetworkDown | Self::TimedOut | Self::SystemNotReady
+                )
+            }
+            ErrorCategory::NameResolution => matches!(self, Self::TryAgain),
+            ErrorCategory::Permission
+            | ErrorCategory::Configuration
+            | ErrorCategory::Unsupported
+            | ErrorCategory::Usage
+            | ErrorCategory::Qos
+            | ErrorCategory::Unknown => false,
+        }
+    }
+
+    /// Whether access was denied, see [`ErrorCategory::Permission`]
+    #[must_use]
+    pub const fn is_permission(self) -> bool {
+        matches!(self.category(), ErrorCategory::Permission)
+    }
+
+    /// Whether Winsock itself is set up wrong, so retrying won't help until the system is fixed,
+    /// see [`ErrorCategory::Configuration`]
+    #[must_use]
+    pub const fn is_configuration(self) -> bool {
+        matches!(self.category(), ErrorCategory::Configuration)
+    }
+
+    /// A hint on what to do about the error
+    #[must_use]
+    pub const fn remediation(self) -> &'static str {
+        match self {
+            Self::SystemNotReady => {
+                "The network subsystem is still starting up, retry after a short delay, \
+                 and check the Winsock DLL and network drivers are installed if it persists"
+            }
+            Self::VersionNotSupported => {
+                "Request a version the Winsock implementation supports, 2.2 is supported by every \
+                 windows since 98"
+            }
+            Self::TasksLimitReached => {
+                "Too many applications are using Winsock, close some or retry later"
+            }
+            Self::OperationInProgress => {
+                "A blocking Winsock call is still running, retry once it completes"
+            }
+            Self::InvalidData => {
+                "A pointer argument was invalid, check the buffers and structures passed in"
+            }
+            Self::NotInitialised => {
+                "Initialize WSA before using Winsock, and make sure it isn't cleaned up early"
+            }
+            Self::InvalidProvider | Self::ProviderFailedInit | Self::InvalidProcedureTable => {
+                "A service provider is broken, reset the Winsock catalog with \
+                 `netsh winsock reset` and restart"
+            }
+            Self::TooManyOpenFiles => "Close sockets that are no longer needed",
+            Self::AddressInUse => "Bind to another port, or set SO_REUSEADDR",
+            _ => match self.category() {
+                ErrorCategory::Pending => "Wait for the operation to complete, then retry",
+                ErrorCategory::Cancelled => "Retry the operation if it is still needed",
+                ErrorCategory::Resources => "Free resources or raise the limit, then retry",
+                ErrorCategory::Permission => {
+                    "Run with the required privileges, or check the firewall and security software"
+                }
+                ErrorCategory::Configuration => {
+                    "Repair the Winsock installation, `netsh winsock reset` resets its catalog"
+                }
+                ErrorCategory::Unsupported => {
+                    "Use a protocol, address family or operation the system supports"
+                }
+                ErrorCategory::Usage => {
+                    "Fix the call, it was made incorrectly or at the wrong time"
+                }
+                ErrorCategory::Network => {
+                    "Check the network connection and the remote host, then retry"
+                }
+                ErrorCategory::NameResolution => "Check the name and the DNS configuration",
+                ErrorCategory::Qos => "Check the Quality of Service configuration",
+                ErrorCategory::Unknown => "Look the code up in the Windows error reference",
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ErrorCategory::{
+        self, Cancelled, Configuration, NameResolution, Network, Pending, Permission, Qos,
+        Resources, Unknown, Unsupported, Usage,
+    };
+    use crate::WsaError;
+
+    /// An error, its category, and whether it is transient, a permission or a configuration error
+    type Row = (WsaError, ErrorCategory, bool, bool, bool);
+
+    const TABLE: &[Row] = &[
+        (WsaError::InvalidHandle, Usage, false, false, false),
+        (WsaError::NotEnoughMemory, Resources, true, false, false),
+        (WsaError::InvalidParameter, Usage, false, false, false),
+        (WsaError::OperationAborted, Cancelled, false, false, false),
+        (WsaError::IoIncomplete, Pending, false, false, false),
+        (WsaError::IoPending, Pending, false, false, false),
+        (WsaError::Interrupted, Cancelled, true, false, false),
+        (WsaError::BadFileHandle, Usage, false, false, false),
+        (WsaError::PermissionDenied, Permission, false, true, false),
+        (WsaError::InvalidData, Usage, false, false, false),
+        (WsaError::InvalidArgument, Usage, false, false, false),
+        (WsaError::TooManyOpenFiles, Resources, false, false, false),
+        (WsaError::WouldBlock, Pending, true, false, false),
+        (WsaError::OperationInProgress, Pending, true, false, false),
+        (WsaError::AlreadyInProgress, Pending, true, false, false),
+        (WsaError::NotASocket, Usage, false, false, false),
+        (
+            WsaError::DestinationAddressRequired,
+            Usage,
+            false,
+            false,
+            false,
+        ),
+        (WsaError::MessageTooLong, Usage, false, false, false),
+        (WsaError::WrongProtocolType, Usage, false, false, false),
+        (WsaError::BadProtocolOption, Usage, false, false, false),
+        (
+            WsaError::ProtocolNotSupported,
+            Unsupported,
+            false,
+            false,
+            false,
+        ),
+        (
+            WsaError::SocketTypeNotSupported,
+            Unsupported,
+            false,
+            false,
+            false,
+        ),
+        (
+            WsaError::OperationNotSupported,
+            Unsupported,
+            false,
+            false,
+            false,
+        ),
+        (
+            WsaError::ProtocolFamilyNotSupported,
+            Unsupported,
+            false,
+            false,
+            false,
+        ),
+        (
+            WsaError::AddressFamilyNotSupported,
+            Unsupported,
+            false,
+            false,
+            false,
+        ),
+        (WsaError::AddressInUse, Network, false, false, false),
+        (WsaError::AddressNotAvailable, Network, false, false, false),
+        (WsaError::NetworkDown, Network, true, false, false),
+        (WsaError::NetworkUnreachable, Network, false, false, false),
+        (WsaError::NetworkReset, Network, false, false, false),
+        (WsaError::ConnectionAborted, Network, false, false, false),
+        (WsaError::ConnectionReset, Network, false, false, false),
+        (WsaError::NoBufferSpace, Resources, true, false, false),
+        (WsaError::AlreadyConnected, Usage, false, false, false),
+        (WsaError::NotConnected, Usage, false, false, false),
+        (WsaError::Shutdown, Usage, false, false, false),
+        (WsaError::TooManyReferences, Resources, false, false, false),
+        (WsaError::TimedOut, Network, true, false, false),
+        (WsaError::ConnectionRefused, Network, false, false, false),
+        (WsaError::CannotTranslateName, Usage, false, false, false),
+        (WsaError::NameTooLong, Usage, false, false, false),
+        (WsaError::HostDown, Network, false, false, false),
+        (WsaError::HostUnreachable, Network, false, false, false),
+        (WsaError::DirectoryNotEmpty, Usage, false, false, false),
+        (WsaError::TasksLimitReached, Resources, true, false, false),
+        (WsaError::UserQuotaExceeded, Resources, false, false, false),
+        (WsaError::DiskQuotaExceeded, Resources, false, false, false),
+        (WsaError::StaleHandle, Usage, false, false, false),
+        (WsaError::ItemIsRemote, Usage, false, false, false),
+        (WsaError::SystemNotReady, Network, true, false, false),
+        (
+            WsaError::VersionNotSupported,
+            Configuration,
+            false,
+            false,
+            true,
+        ),
+        (WsaError::NotInitialised, Usage, false, false, false),
+        (WsaError::GracefulShutdown, Network, false, false, false),
+        (WsaError::NoMore, NameResolution, false, false, false),
+        (WsaError::Cancelled, Cancelled, false, false, false),
+        (
+            WsaError::InvalidProcedureTable,
+            Configuration,
+            false,
+            false,
+            true,
+        ),
+        (WsaError::InvalidProvider, Configuration, false, false, true),
+        (
+            WsaError::ProviderFailedInit,
+            Configuration,
+            false,
+            false,
+            true,
+        ),
+        (
+            WsaError::SystemCallFailure,
+            Configuration,
+            false,
+            false,
+            true,
+        ),
+        (
+            WsaError::ServiceNotFound,
+            NameResolution,
+            false,
+            false,
+            false,
+        ),
+        (WsaError::TypeNotFound, NameResolution, false, false, false),
+        (WsaError::NoMoreResults, NameResolution, false, false, false),
+        (WsaError::LookupCancelled, Cancelled, false, false, false),
+        (WsaError::QueryRefused, Permission, false, true, false),
+        (WsaError::HostNotFound, NameResolution, false, false, false),
+        (WsaError::TryAgain, NameResolution, true, false, false),
+        (WsaError::NoRecovery, NameResolution, false, false, false),
+        (WsaError::NoData, NameResolution, false, false, false),
+        (WsaError::QosReceivers, Qos, false, false, false),
+        (WsaError::QosSenders, Qos, false, false, false),
+        (WsaError::QosNoSenders, Qos, false, false, false),
+        (WsaError::QosNoReceivers, Qos, false, false, false),
+        (WsaError::QosRequestConfirmed, Qos, false, false, false),
+        (WsaError::QosAdmissionFailure, Qos, false, false, false),
+        (WsaError::QosPolicyFailure, Qos, false, false, false),
+        (WsaError::QosBadStyle, Qos, false, false, false),
+        (WsaError::QosBadObject, Qos, false, false, false),
+        (WsaError::QosTrafficControlError, Qos, false, false, false),
+        (WsaError::QosGenericError, Qos, false, false, false),
+        (WsaError::QosServiceTypeError, Qos, false, false, false),
+        (WsaError::QosFlowspecError, Qos, false, false, false),
+        (WsaError::QosInvalidProviderBuffer, Qos, false, false, false),
+        (WsaError::QosInvalidFilterStyle, Qos, false, false, false),
+        (WsaError::QosInvalidFilterType, Qos, false, false, false),
+        (WsaError::QosIncorrectFilterCount, Qos, false, false, false),
+        (WsaError::QosInvalidObjectLength, Qos, false, false, false),
+        (WsaError::QosIncorrectFlowCount, Qos, false, false, false),
+        (WsaError::QosUnknownObject, Qos, false, false, false),
+        (WsaError::QosInvalidPolicyObject, Qos, false, false, false),
+        (WsaError::QosInvalidFlowDescriptor, Qos, false, false, false),
+        (
+            WsaError::QosInvalidProviderFlowspec,
+            Qos,
+            false,
+            false,
+            false,
+        ),
+        (
+            WsaError::QosInvalidProviderFilterspec,
+            Qos,
+            false,
+            false,
+            false,
+        ),
+        (
+            WsaError::QosInvalidShapeDiscardMode,
+            Qos,
+            false,
+            false,
+            false,
+        ),
+        (WsaError::QosInvalidShapingRate, Qos, false, false, false),
+        (WsaError::QosReservedPolicyElement, Qos, false, false, false),
+        (WsaError::Other(42), Unknown, false, false, false),
+    ];
+
+    #[test]
+    fn table_covers_catalogue() {
+        let catalogued: Vec<_> = TABLE.iter().map(|row| row.0).collect();
+        assert_eq!(&catalogued[..catalogued.len() - 1], WsaError::ALL);
+    }
+
+    #[test]
+    fn classifications() {
+        for &(err, category, transient, permission, configuration) in TABLE {
+            assert_eq!(err.category(), category, "{err:?}");
+            assert_eq!(err.is_transient(), transient, "{err:?}");
+            assert_eq!(err.is_permission(), permission, "{err:?}");
+            assert_eq!(err.is_configuration(), configuration, "{err:?}");
+        }
+    }
+
+    #[test]
+    fn remediations() {
+        for &(err, ..) in TABLE {
+            assert_ne!(err.remediation(), "", "{err:?}");
+        }
+        assert!(WsaError::SystemNotReady
+            .remediation()
+            .contains("retry after a short delay"));
+        assert!(WsaError::ProviderFailedInit
+            .remediation()
+            .contains("netsh winsock reset"));
+        assert_eq!(
+            WsaError::HostNotFound.remediation(),
+            "Check the name and the DNS configuration"
+        );
+    }
+}
@@ -0,0 +1,56 @@
+//! This module holds [`CleanupPolicy`], what a [`WsaRaii`](crate::WsaRaii) does when `WSACleanup` fails
+
+use crate::{trace, WsaError};
+use std::{
+    fmt::{Debug, Formatter, Result as FmtResult},
+    sync::Arc,
+    thread,
+};
+
+/// What to do when `WSACleanup` fails while dropping a [`WsaRaii`](crate::WsaRaii)
+#[derive(Clone, Default)]
+pub enum CleanupPolicy {
+    /// Silently ignore the failure
+    #[default]
+    Ignore,
+    /// Report the failure through `tracing` or `log` when either feature is enabled, to stderr otherwise
+    Log,
+    /// Hand the failure to a callback
+    Callback(Arc<dyn Fn(WsaError) + Send + Sync>),
+    /// Panic in debug builds, ignore the failure in release builds.\
+    /// Never panics while already panicking, as that would abort the process
+    PanicInDebug,
+}
+
+impl CleanupPolicy {
+    /// A policy handing failures to `callback`
+    pub fn callback(callback: impl Fn(WsaError) + Send + Sync + 'static) -> Self {
+        Self::Callback(Arc::new(callback))
+    }
+
+    pub(crate) fn handle(&self, err: WsaError) {
+        match self {
+            Self::Ignore => {}
+            Self::Log => trace::cleanup_failed(err),
+            Self::Callback(callback) => callback(err),
+            Self::PanicInDebug => {
+                assert!(
+                    !cfg!(debug_assertions) || thread::panicking(),
+                    "failed to clean up WSA: {}",
+                    err
+                );
+            }
+        }
+    }
+}
+
+impl Debug for CleanupPolicy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Ignore => f.write_str("Ignore"),
+            Self::Log => f.write_str("Log"),
+            Self::Callback(_) => f.write_str("Callback(..)"),
+            Self::PanicInDebug => f.write_str("PanicInDebug"),
+        }
+    }
+}
@@ -0,0 +1,335 @@
+//! This module holds the [`Dynamic`] backend, which loads Winsock at runtime instead of linking it,
+//! so a missing `ws2_32` is reported as a [`LoadError`] rather than failing to start the process
+
+use crate::{
+    protocol,
+    sys::{self, WSADATA},
+    Backend, ProtocolInfo, Result, WsaError, WsaInfo, WsaVersion,
+};
+use libloading::Library;
+use std::{
+    error::Error,
+    ffi::{OsStr, OsString},
+    fmt::{Debug, Display, Formatter, Result as FmtResult},
+    io,
+    os::raw::c_int,
+    path::Path,
+    sync::Arc,
+};
+
+type WsaStartupFn = unsafe extern "system" fn(u16, *mut WSADATA) -> c_int;
+type WsaCleanupFn = unsafe extern "system" fn() -> c_int;
+type WsaGetLastErrorFn = unsafe extern "system" fn() -> c_int;
+type WsaSetLastErrorFn = unsafe extern "system" fn(c_int);
+type WsaEnumProtocolsFn = unsafe extern "system" fn(*const c_int, *mut u32, *mut u32) -> c_int;
+
+/// Winsock as loaded at runtime from a library exporting `WSAStartup`, `WSACleanup`
+/// and `WSAGetLastError`.
+///
+/// `WSAEnumProtocolsW` and `WSASetLastError` are loaded too when the library exports them.
+/// Clones share the same loaded library, which stays loaded until the last clone is dropped
+#[derive(Clone)]
+pub struct Dynamic(Arc<Loaded>);
+
+struct Loaded {
+    name: OsString,
+    startup: WsaStartupFn,
+    cleanup: WsaCleanupFn,
+    last_error: WsaGetLastErrorFn,
+    set_last_error: Option<WsaSetLastErrorFn>,
+    enum_protocols: Option<WsaEnumProtocolsFn>,
+    // Declared last so it is unloaded only after nothing can call into it
+    _library: Library,
+}
+
+/// Why [`Dynamic`] couldn't load Winsock
+#[derive(Debug)]
+pub enum LoadError {
+    /// The library couldn't be found or loaded
+    Library {
+        /// The name the library was loaded by
+        name: OsString,
+        /// Why loading it failed
+        source: libloading::Error,
+    },
+    /// The library doesn't export one of the functions needed
+    Symbol {
+        /// The name the library was loaded by
+        name: OsString,
+        /// The missing function
+        symbol: &'static str,
+        /// Why resolving it failed
+        source: libloading::Error,
+    },
+}
+
+impl Dynamic {
+    /// The name of the Winsock library on windows
+    pub const WS2_32: &'static str = "ws2_32.dll";
+
+    /// Loads Winsock from `ws2_32.dll`
+    /// # Errors
+    /// Returns a [`LoadError`] if the library is missing or doesn't export the Winsock functions
+    pub fn ws2_32() -> Result<Self, LoadError> {
+        Self::load(Self::WS2_32)
+    }
+
+    /// Loads Winsock from the library named `name`, searched for the way the platform
+    /// searches for libraries, or at that path
+    /// # Errors
+    /// Returns a [`LoadError`] if the library is missing or doesn't export the Winsock functions
+    pub fn load(name: impl AsRef<OsStr>) -> Result<Self, LoadError> {
+        let name = name.as_ref().to_owned();
+        // Loading runs the library's initialization, which for Winsock is sound
+        let library = match unsafe { Library::new(&name) } {
+            Ok(library) => library,
+            Err(source) => return Err(LoadError::Library { name, source }),
+        };
+        // The signatures match the ones Winsock declares
+        let (startup, cleanup, last_error) = unsafe {
+            (
+                resolve(&library, &name, "WSAStartup")?,
+                resolve(&library, &name, "WSACleanup")?,
+                resolve(&library, &name, "WSAGetLastError")?,
+            )
+        };
+        // Only needed for listing protocols and setting errors, so they may be missing
+        let enum_protocols = unsafe { resolve(&library, &name, "WSAEnumProtocolsW") }.ok();
+        let set_last_error = unsafe { resolve(&library, &name, "WSASetLastError") }.ok();
+        Ok(Self(Arc::new(Loaded {
+            name,
+            startup,
+            cleanup,
+            last_error,
+            set_last_error,
+            enum_protocols,
+            _library: library,
+        })))
+    }
+
+    /// The name the library was loaded by
+    #[must_use]
+    pub fn name(&self) -> &OsStr {
+        &self.0.name
+    }
+
+    /// Sets the calling thread's last error through the library's `WSASetLastError`,
+    /// [`None`] clears it. Has no effect if the library doesn't export it
+    pub fn set_last_error(&self, err: Option<WsaError>) {
+        if let Some(set_last_error) = self.0.set_last_error {
+            unsafe { set_last_error(err.map_or(0, WsaError::code)) }
+        }
+    }
+}
+
+/// Resolves the function `symbol` from `library`, loaded by `name`
+///
+/// # Safety
+/// `T` must be the type of the function the library exports as `symbol`
+unsafe fn resolve<T: Copy>(
+    library: &Library,
+    name: &OsStr,
+    symbol: &'static str,
+) -> Result<T, LoadError> {
+    match unsafe { library.get::<T>(symbol.as_bytes()) } {
+        Ok(function) => Ok(*function),
+        Err(source) => Err(LoadError::Symbol {
+            name: name.to_owned(),
+            symbol,
+            source,
+        }),
+    }
+}
+
+impl Backend for Dynamic {
+    fn startup(&self, version: WsaVersion) -> Result<WsaInfo> {
+        let mut data: WSADATA = unsafe { std::mem::zeroed() };
+        let result = unsafe { (self.0.startup)(version.to_word(), &raw mut data) };
+        if result == 0 {
+            Ok(WsaInfo::from(&data))
+        } else {
+            Err(result.into())
+        }
+    }
+
+    fn cleanup(&self) -> Result<()> {
+        if unsafe { (self.0.cleanup)() } == 0 {
+            Ok(())
+        } else {
+            Err(self
+                .last_error()
+                .unwrap_or(WsaError::Other(sys::SOCKET_ERROR)))
+        }
+    }
+
+    fn last_error(&self) -> Option<WsaError> {
+        match unsafe { (self.0.last_error)() } {
+            0 => None,
+            code => Some(code.into()),
+        }
+    }
+
+    fn protocols(&self) -> Result<Vec<ProtocolInfo>> {
+        let enum_protocols = self
+            .0
+            .enum_protocols
+            .ok_or(WsaError::OperationNotSupported)?;
+        protocol::enumerate(
+            |buffer, len| unsafe { enum_protocols(std::ptr::null(), buffer, len) },
+            || self.last_error(),
+        )
+    }
+}
+
+impl Debug for Dynamic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_tuple("Dynamic").field(&self.0.name).finish()
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Library { source, .. } | Self::Symbol { source, .. } => Some(source),
+        }
+    }
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Library { name, .. } => {
+                write!(
+                    f,
+                    "couldn't load Winsock from {}",
+                    Path::new(name).display()
+                )
+            }
+            Self::Symbol { name, symbol, .. } => {
+                write!(f, "{} doesn't export {symbol}", Path::new(name).display())
+            }
+        }
+    }
+}
+
+/// Wraps the [`LoadError`] in an [`io::Error`] of kind [`NotFound`](io::ErrorKind::NotFound)
+impl From<LoadError> for io::Error {
+    fn from(err: LoadError) -> Self {
+        Self::new(io::ErrorKind::NotFound, err)
+    }
+}
+
+/// The raw Winsock calls when the crate is built without `winapi` and `windows-sys`,
+/// going through `ws2_32.dll` loaded on first use and kept loaded for the rest of the process.
+///
+/// If it can't be loaded, startups fail with [`WsaError::SystemNotReady`]
+/// and every other call fails with [`WsaError::NotInitialised`], as nothing could have started up.
+/// Why it couldn't be loaded is kept for [`Winsock::load_error`](crate::Winsock::load_error)
+#[cfg(all(windows, not(any(feature = "winapi", feature = "windows-sys"))))]
+#[allow(non_snake_case, clippy::upper_case_acronyms)]
+pub mod process {
+    use super::{Dynamic, LoadError, Loaded};
+    use std::{
+        os::raw::{c_char, c_int, c_ushort},
+        sync::OnceLock,
+    };
+
+    pub const WSADESCRIPTION_LEN: usize = 256;
+    pub const WSASYS_STATUS_LEN: usize = 128;
+
+    /// The C `WSADATA`, whose field order depends on the pointer width
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct WSADATA {
+        pub wVersion: u16,
+        pub wHighVersion: u16,
+        #[cfg(target_pointer_width = "32")]
+        pub szDescription: [c_char; WSADESCRIPTION_LEN + 1],
+        #[cfg(target_pointer_width = "32")]
+        pub szSystemStatus: [c_char; WSASYS_STATUS_LEN + 1],
+        pub iMaxSockets: c_ushort,
+        pub iMaxUdpDg: c_ushort,
+        pub lpVendorInfo: *mut c_char,
+        #[cfg(target_pointer_width = "64")]
+        pub szDescription: [c_char; WSADESCRIPTION_LEN + 1],
+        #[cfg(target_pointer_width = "64")]
+        pub szSystemStatus: [c_char; WSASYS_STATUS_LEN + 1],
+    }
+
+    /// Mirrors the size and alignment of the C `WSAPROTOCOL_INFOW`
+    #[repr(C)]
+    pub struct WSAPROTOCOL_INFOW([u32; 157]);
+
+    pub const INVALID_SOCKET: usize = !0;
+    pub const SOCKET_ERROR: c_int = -1;
+
+    const WSASYSNOTREADY: c_int = 10091;
+    const WSANOTINITIALISED: c_int = 10093;
+
+    fn loaded() -> &'static Result<Dynamic, LoadError> {
+        static WS2_32: OnceLock<Result<Dynamic, LoadError>> = OnceLock::new();
+        WS2_32.get_or_init(Dynamic::ws2_32)
+    }
+
+    fn ws2_32() -> Option<&'static Loaded> {
+        loaded().as_ref().ok().map(|dynamic| &*dynamic.0)
+    }
+
+    /// Why `ws2_32.dll` couldn't be loaded, trying to load it if it wasn't yet
+    pub fn load_error() -> Option<&'static LoadError> {
+        loaded().as_ref().err()
+    }
+
+    pub unsafe fn WSAStartup(version: u16, data: *mut WSADATA) -> c_int {
+        ws2_32().map_or(WSASYSNOTREADY, |ws2_32| unsafe {
+            (ws2_32.startup)(version, data)
+        })
+    }
+
+    pub unsafe fn WSACleanup() -> c_int {
+        ws2_32().map_or(SOCKET_ERROR, |ws2_32| unsafe { (ws2_32.cleanup)() })
+    }
+
+    pub unsafe fn WSAEnumProtocolsW(
+        protocols: *const c_int,
+        buffer: *mut WSAPROTOCOL_INFOW,
+        len: *mut u32,
+    ) -> c_int {
+        ws2_32()
+            .and_then(|ws2_32| ws2_32.enum_protocols)
+            .map_or(SOCKET_ERROR, |enum_protocols| unsafe {
+                enum_protocols(protocols, buffer.cast(), len)
+            })
+    }
+
+    pub unsafe fn WSAGetLastError() -> c_int {
+        ws2_32().map_or(WSANOTINITIALISED, |ws2_32| unsafe { (ws2_32.last_error)() })
+    }
+
+    pub unsafe fn WSASetLastError(error: c_int) {
+        if let Some(set_last_error) = ws2_32().and_then(|ws2_32| ws2_32.set_last_error) {
+            unsafe { set_last_error(error) }
+        }
+    }
+}
+
+#[cfg(all(test, windows))]
+mod tests {
+    use super::Dynamic;
+    use crate::{Winsock, WsaInitializer, WsaVersion};
+
+    #[test]
+    fn loads_ws2_32() {
+        let dynamic = Dynamic::ws2_32().unwrap();
+        let wsa = WsaInitializer::with_backend(dynamic).init().unwrap();
+        assert_eq!(wsa.info().version(), WsaVersion::V2_2);
+        assert_eq!(wsa.try_clean(), Ok(()));
+    }
+
+    #[test]
+    fn process_loads_ws2_32() {
+        assert!(Winsock::load_error().is_none());
+        let wsa = WsaInitializer::default().init().unwrap();
+        assert_eq!(wsa.try_clean(), Ok(()));
+    }
+}
@@ -0,0 +1,567 @@
+//! This module holds [`WsaError`], the catalogue of Windows Sockets error codes
+
+use std::{
+    convert::TryFrom,
+    error::Error,
+    fmt::{Display, Formatter, Result as FmtResult},
+    io::{self, ErrorKind},
+};
+
+macro_rules! catalogue {
+    ($($variant:ident = $code:literal, $name:literal, $message:literal;)*) => {
+        /// An Error returned from Winsock, covering every documented Windows Sockets error code
+        ///
+        /// Codes outside the catalogue are kept as they are in [`WsaError::Other`],
+        /// so converting from and back to an `i32` never loses information
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum WsaError {
+            $(
+                #[doc = concat!("`", $name, "` (", $code, "): ", $message)]
+                $variant,
+            )*
+            /// A code that isn't part of the catalogue
+            Other(i32),
+        }
+
+        impl WsaError {
+            /// Every error in the catalogue, ordered by code
+            pub const ALL: &'static [Self] = &[$(Self::$variant),*];
+
+            /// The numeric error code, as returned by `WSAGetLastError`
+            #[must_use]
+            pub const fn code(self) -> i32 {
+                match self {
+                    $(Self::$variant => $code,)*
+                    Self::Other(code) => code,
+                }
+            }
+
+            /// The symbolic name of the error code, such as `WSASYSNOTREADY`,
+            /// [`None`] for codes outside the catalogue
+            #[must_use]
+            pub const fn name(self) -> Option<&'static str> {
+                match self {
+                    $(Self::$variant => Some($name),)*
+                    Self::Other(_) => None,
+                }
+            }
+
+            /// A short description of the error, such as
+            /// `The underlying network subsystem is not ready for network communication.`,
+            /// [`None`] for codes outside the catalogue
+            #[must_use]
+            pub const fn message(self) -> Option<&'static str> {
+                match self {
+                    $(Self::$variant => Some($message),)*
+                    Self::Other(_) => None,
+                }
+            }
+
+            /// The error's entry in Microsoft's documentation,
+            /// the list of every Windows Sockets error code for codes outside the catalogue
+            #[must_use]
+            pub const fn docs_url(self) -> &'static str {
+                match self {
+                    $(Self::$variant => concat!(
+                        "https://learn.microsoft.com/en-us/windows/win32/winsock/windows-sockets-error-codes-2#",
+                        $name
+                    ),)*
+                    Self::Other(_) => "https://learn.microsoft.com/en-us/windows/win32/winsock/windows-sockets-error-codes-2",
+                }
+            }
+        }
+
+        impl From<i32> for WsaError {
+            fn from(err_code: i32) -> Self {
+                match err_code {
+                    $($code => Self::$variant,)*
+                    code => Self::Other(code),
+                }
+            }
+        }
+    };
+}
+
+catalogue! {
+    InvalidHandle = 6, "WSA_INVALID_HANDLE", "Specified event object handle is invalid.";
+    NotEnoughMemory = 8, "WSA_NOT_ENOUGH_MEMORY", "Insufficient memory available.";
+    InvalidParameter = 87, "WSA_INVALID_PARAMETER", "One or more parameters are invalid.";
+    OperationAborted = 995, "WSA_OPERATION_ABORTED", "Overlapped operation aborted.";
+    IoIncomplete = 996, "WSA_IO_INCOMPLETE", "Overlapped I/O event object not in signaled state.";
+    IoPending = 997, "WSA_IO_PENDING", "Overlapped operations will complete later.";
+    Interrupted = 10004, "WSAEINTR", "Interrupted function call.";
+    BadFileHandle = 10009, "WSAEBADF", "File handle is not valid.";
+    PermissionDenied = 10013, "WSAEACCES", "Permission denied.";
+    InvalidData = 10014, "WSAEFAULT", "Bad address.";
+    InvalidArgument = 10022, "WSAEINVAL", "Invalid argument.";
+    TooManyOpenFiles = 10024, "WSAEMFILE", "Too many open files.";
+    WouldBlock = 10035, "WSAEWOULDBLOCK", "Resource temporarily unavailable.";
+    OperationInProgress = 10036, "WSAEINPROGRESS", "Operation now in progress.";
+    AlreadyInProgress = 10037, "WSAEALREADY", "Operation already in progress.";
+    NotASocket = 10038, "WSAENOTSOCK", "Socket operation on nonsocket.";
+    DestinationAddressRequired = 10039, "WSAEDESTADDRREQ", "Destination address required.";
+    MessageTooLong = 10040, "WSAEMSGSIZE", "Message too long.";
+    WrongProtocolType = 10041, "WSAEPROTOTYPE", "Protocol wrong type for socket.";
+    BadProtocolOption = 10042, "WSAENOPROTOOPT", "Bad protocol option.";
+    ProtocolNotSupported = 10043, "WSAEPROTONOSUPPORT", "Protocol not supported.";
+    SocketTypeNotSupported = 10044, "WSAESOCKTNOSUPPORT", "Socket type not supported.";
+    OperationNotSupported = 10045, "WSAEOPNOTSUPP", "Operation not supported.";
+    ProtocolFamilyNotSupported = 10046, "WSAEPFNOSUPPORT", "Protocol family not supported.";
+    AddressFamilyNotSupported = 10047, "WSAEAFNOSUPPORT", "Address family not supported by protocol family.";
+    AddressInUse = 10048, "WSAEADDRINUSE", "Address already in use.";
+    AddressNotAvailable = 10049, "WSAEADDRNOTAVAIL", "Cannot assign requested address.";
+    NetworkDown = 10050, "WSAENETDOWN", "Network is down.";
+    NetworkUnreachable = 10051, "WSAENETUNREACH", "Network is unreachable.";
+    NetworkReset = 10052, "WSAENETRESET", "Network dropped connection on reset.";
+    ConnectionAborted = 10053, "WSAECONNABORTED", "Software caused connection abort.";
+    ConnectionReset = 10054, "WSAECONNRESET", "Connection reset by peer.";
+    NoBufferSpace = 10055, "WSAENOBUFS", "No buffer space available.";
+    AlreadyConnected = 10056, "WSAEISCONN", "Socket is already connected.";
+    NotConnected = 10057, "WSAENOTCONN", "Socket is not connected.";
+    Shutdown = 10058, "WSAESHUTDOWN", "Cannot send after socket shutdown.";
+    TooManyReferences = 10059, "WSAETOOMANYREFS", "Too many references.";
+    TimedOut = 10060, "WSAETIMEDOUT", "Connection timed out.";
+    ConnectionRefused = 10061, "WSAECONNREFUSED", "Connection refused.";
+    CannotTranslateName = 10062, "WSAELOOP", "Cannot translate name.";
+    NameTooLong = 10063, "WSAENAMETOOLONG", "Name too long.";
+    HostDown = 10064, "WSAEHOSTDOWN", "Host is down.";
+    HostUnreachable = 10065, "WSAEHOSTUNREACH", "No route to host.";
+    DirectoryNotEmpty = 10066, "WSAENOTEMPTY", "Directory not empty.";
+    TasksLimitReached = 10067, "WSAEPROCLIM", "A limit on the number of tasks supported by the Windows Sockets implementation has been reached.";
+    UserQuotaExceeded = 10068, "WSAEUSERS", "User quota exceeded.";
+    DiskQuotaExceeded = 10069, "WSAEDQUOT", "Disk quota exceeded.";
+    StaleHandle = 10070, "WSAESTALE", "Stale file handle reference.";
+    ItemIsRemote = 10071, "WSAEREMOTE", "Item is remote.";
+    SystemNotReady = 10091, "WSASYSNOTREADY", "The underlying network subsystem is not ready for network communication.";
+    VersionNotSupported = 10092, "WSAVERNOTSUPPORTED", "The version of Windows Sockets support requested is not provided by this particular Windows Sockets implementation.";
+    NotInitialised = 10093, "WSANOTINITIALISED", "Successful WSAStartup not yet performed.";
+    GracefulShutdown = 10101, "WSAEDISCON", "Graceful shutdown in progress.";
+    NoMore = 10102, "WSAENOMORE", "No more results.";
+    Cancelled = 10103, "WSAECANCELLED", "Call has been canceled.";
+    InvalidProcedureTable = 10104, "WSAEINVALIDPROCTABLE", "Procedure call table is invalid.";
+    InvalidProvider = 10105, "WSAEINVALIDPROVIDER", "Service provider is invalid.";
+    ProviderFailedInit = 10106, "WSAEPROVIDERFAILEDINIT", "Service provider failed to initialize.";
+    SystemCallFailure = 10107, "WSASYSCALLFAILURE", "System call failure.";
+    ServiceNotFound = 10108, "WSASERVICE_NOT_FOUND", "Service not found.";
+    TypeNotFound = 10109, "WSATYPE_NOT_FOUND", "Class type not found.";
+    NoMoreResults = 10110, "WSA_E_NO_MORE", "No more results.";
+    LookupCancelled = 10111, "WSA_E_CANCELLED", "Call was canceled.";
+    QueryRefused = 10112, "WSAEREFUSED", "Database query was refused.";
+    HostNotFound = 11001, "WSAHOST_NOT_FOUND", "Host not found.";
+    TryAgain = 11002, "WSATRY_AGAIN", "Nonauthoritative host not found.";
+    NoRecovery = 11003, "WSANO_RECOVERY", "This is a nonrecoverable error.";
+    NoData = 11004, "WSANO_DATA", "Valid name, no data record of requested type.";
+    QosReceivers = 11005, "WSA_QOS_RECEIVERS", "QoS receivers.";
+    QosSenders = 11006, "WSA_QOS_SENDERS", "QoS senders.";
+    QosNoSenders = 11007, "WSA_QOS_NO_SENDERS", "No QoS senders.";
+    QosNoReceivers = 11008, "WSA_QOS_NO_RECEIVERS", "QoS no receivers.";
+    QosRequestConfirmed = 11009, "WSA_QOS_REQUEST_CONFIRMED", "QoS request confirmed.";
+    QosAdmissionFailure = 11010, "WSA_QOS_ADMISSION_FAILURE", "QoS admission error.";
+    QosPolicyFailure = 11011, "WSA_QOS_POLICY_FAILURE", "QoS policy failure.";
+    QosBadStyle = 11012, "WSA_QOS_BAD_STYLE", "QoS bad style.";
+    QosBadObject = 11013, "WSA_QOS_BAD_OBJECT", "QoS bad object.";
+    QosTrafficControlError = 11014, "WSA_QOS_TRAFFIC_CTRL_ERROR", "QoS traffic control error.";
+    QosGenericError = 11015, "WSA_QOS_GENERIC_ERROR", "QoS generic error.";
+    QosServiceTypeError = 11016, "WSA_QOS_ESERVICETYPE", "QoS service type error.";
+    QosFlowspecError = 11017, "WSA_QOS_EFLOWSPEC", "QoS flowspec error.";
+    QosInvalidProviderBuffer = 11018, "WSA_QOS_EPROVSPECBUF", "Invalid QoS provider buffer.";
+    QosInvalidFilterStyle = 11019, "WSA_QOS_EFILTERSTYLE", "Invalid QoS filter style.";
+    QosInvalidFilterType = 11020, "WSA_QOS_EFILTERTYPE", "Invalid QoS filter type.";
+    QosIncorrectFilterCount = 11021, "WSA_QOS_EFILTERCOUNT", "Incorrect QoS filter count.";
+    QosInvalidObjectLength = 11022, "WSA_QOS_EOBJLENGTH", "Invalid QoS object length.";
+    QosIncorrectFlowCount = 11023, "WSA_QOS_EFLOWCOUNT", "Incorrect QoS flow count.";
+    QosUnknownObject = 11024, "WSA_QOS_EUNKOWNPSOBJ", "Unrecognized QoS object.";
+    QosInvalidPolicyObject = 11025, "WSA_QOS_EPOLICYOBJ", "Invalid QoS policy object.";
+    QosInvalidFlowDescriptor = 11026, "WSA_QOS_EFLOWDESC", "Invalid QoS flow descriptor.";
+    QosInvalidProviderFlowspec = 11027, "WSA_QOS_EPSFLOWSPEC", "Invalid QoS provider-specific flowspec.";
+    QosInvalidProviderFilterspec = 11028, "WSA_QOS_EPSFILTERSPEC", "Invalid QoS provider-specific filterspec.";
+    QosInvalidShapeDiscardMode = 11029, "WSA_QOS_ESDMODEOBJ", "Invalid QoS shape discard mode object.";
+    QosInvalidShapingRate = 11030, "WSA_QOS_ESHAPERATEOBJ", "Invalid QoS shaping rate object.";
+    QosReservedPolicyElement = 11031, "WSA_QOS_RESERVED_PETYPE", "Reserved policy QoS element type.";
+}
+
+impl From<WsaError> for i32 {
+    fn from(err: WsaError) -> Self {
+        err.code()
+    }
+}
+
+impl WsaError {
+    /// The [`io::ErrorKind`] that best describes this error, [`ErrorKind::Other`] if none does
+    #[must_use]
+    pub const fn kind(self) -> ErrorKind {
+        use WsaError::{
+            AddressFamilyNotSupported, AddressInUse, AddressNotAvailable, BadFileHandle,
+            BadProtocolOption, ConnectionAborted, ConnectionRefused, ConnectionReset,
+            DestinationAddressRequired, DirectoryNotEmpty, DiskQuotaExceeded, HostDown,
+            HostNotFound, HostUnreachable, Interrupted, InvalidArgument, InvalidData,
+            InvalidHandle, InvalidParameter, MessageTooLong, NameTooLong, NetworkDown,
+            NetworkReset, NetworkUnreachable, NoBufferSpace, NoData, NotASocket, NotConnected,
+            NotEnoughMemory, OperationNotSupported, PermissionDenied, ProtocolFamilyNotSupported,
+            ProtocolNotSupported, ServiceNotFound, Shutdown, SocketTypeNotSupported, StaleHandle,
+            SystemNotReady, TimedOut, TypeNotFound, UserQuotaExceeded, VersionNotSupported,
+            WouldBlock, WrongProtocolType,
+        };
+
+        match self {
+            Interrupted => ErrorKind::Interrupted,
+            PermissionDenied => ErrorKind::PermissionDenied,
+            InvalidHandle
+            | InvalidParameter
+            | BadFileHandle
+            | InvalidData
+            | InvalidArgument
+            | NotASocket
+            | DestinationAddressRequired
+            | MessageTooLong
+            | BadProtocolOption
+            | NameTooLong => ErrorKind::InvalidInput,
+            NotEnoughMemory | NoBufferSpace => ErrorKind::OutOfMemory,
+            WouldBlock => ErrorKind::WouldBlock,
+            WrongProtocolType
+            | ProtocolNotSupported
+            | SocketTypeNotSupported
+            | OperationNotSupported
+            | ProtocolFamilyNotSupported
+            | AddressFamilyNotSupported
+            | VersionNotSupported => ErrorKind::Unsupported,
+            AddressInUse => ErrorKind::AddrInUse,
+            AddressNotAvailable => ErrorKind::AddrNotAvailable,
+            NetworkDown | SystemNotReady => ErrorKind::NetworkDown,
+            NetworkUnreachable => ErrorKind::NetworkUnreachable,
+            ConnectionAborted => ErrorKind::ConnectionAborted,
+            NetworkReset | ConnectionReset => ErrorKind::ConnectionReset,
+            NotConnected => ErrorKind::NotConnected,
+            Shutdown => ErrorKind::BrokenPipe,
+            TimedOut => ErrorKind::TimedOut,
+            ConnectionRefused => ErrorKind::ConnectionRefused,
+            HostDown | HostUnreachable => ErrorKind::HostUnreachable,
+            DirectoryNotEmpty => ErrorKind::DirectoryNotEmpty,
+            UserQuotaExceeded | DiskQuotaExceeded => ErrorKind::QuotaExceeded,
+            StaleHandle => ErrorKind::StaleNetworkFileHandle,
+            HostNotFound | ServiceNotFound | TypeNotFound | NoData => ErrorKind::NotFound,
+            _ => ErrorKind::Other,
+        }
+    }
+
+    /// Recognizes a raw OS error code as a Winsock one.\
+    /// Codes below 10000 are shared with the rest of windows, so elsewhere they aren't Winsock codes
+    fn from_raw_os_error(code: i32) -> Option<Self> {
+        match Self::from(code) {
+            Self::Other(_) => None,
+            _ if !cfg!(windows) && code < 10000 => None,
+            err => Some(err),
+        }
+    }
+}
+
+impl Error for WsaError {}
+
+/// Wraps the [`WsaError`] in an [`io::Error`] of its [`kind`](WsaError::kind),
+/// get it back through `WsaError::try_from`
+impl From<WsaError> for io::Error {
+    fn from(err: WsaError) -> Self {
+        Self::new(err.kind(), err)
+    }
+}
+
+/// Recognizes an [`io::Error`] wrapping a [`WsaError`] or holding a Winsock raw OS error code,
+/// as `std::net` reports them on windows.\
+/// Gives back the [`io::Error`] if it isn't a Winsock one
+impl TryFrom<io::Error> for WsaError {
+    type Error = io::Error;
+
+    fn try_from(err: io::Error) -> Result<Self, Self::Error> {
+        Self::try_from(&err).ok().ok_or(err)
+    }
+}
+
+/// Like the owned conversion, but only borrows the [`io::Error`] and gives back the reference
+impl<'a> TryFrom<&'a io::Error> for WsaError {
+    type Error = &'a io::Error;
+
+    fn try_from(err: &'a io::Error) -> Result<Self, Self::Error> {
+        err.get_ref()
+            .and_then(|inner| inner.downcast_ref::<Self>().copied())
+            .or_else(|| err.raw_os_error().and_then(Self::from_raw_os_error))
+            .ok_or(err)
+    }
+}
+
+/// A single line, `WSASYSNOTREADY (10091): The underlying network subsystem is not ready ...`.\
+/// The alternate form, `{:#}`, adds a line with a hint on what to do and one linking to the docs
+impl Display for WsaError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match (self.name(), self.message()) {
+            (Some(name), Some(message)) => write!(f, "{name} ({}): {message}", self.code())?,
+            _ => write!(f, "unknown Winsock error ({})", self.code())?,
+        }
+        if f.alternate() {
+            write!(f, "\nhint: {}\nsee {}", self.remediation(), self.docs_url())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WsaError;
+    use crate::golden::golden;
+    use std::{
+        convert::TryFrom,
+        fmt::Write,
+        io::{self, ErrorKind},
+    };
+
+    /// Every documented Windows Sockets error code and its symbolic name
+    const TABLE: &[(i32, &str)] = &[
+        (6, "WSA_INVALID_HANDLE"),
+        (8, "WSA_NOT_ENOUGH_MEMORY"),
+        (87, "WSA_INVALID_PARAMETER"),
+        (995, "WSA_OPERATION_ABORTED"),
+        (996, "WSA_IO_INCOMPLETE"),
+        (997, "WSA_IO_PENDING"),
+        (10004, "WSAEINTR"),
+        (10009, "WSAEBADF"),
+        (10013, "WSAEACCES"),
+        (10014, "WSAEFAULT"),
+        (10022, "WSAEINVAL"),
+        (10024, "WSAEMFILE"),
+        (10035, "WSAEWOULDBLOCK"),
+        (10036, "WSAEINPROGRESS"),
+        (10037, "WSAEALREADY"),
+        (10038, "WSAENOTSOCK"),
+        (10039, "WSAEDESTADDRREQ"),
+        (10040, "WSAEMSGSIZE"),
+        (10041, "WSAEPROTOTYPE"),
+        (10042, "WSAENOPROTOOPT"),
+        (10043, "WSAEPROTONOSUPPORT"),
+        (10044, "WSAESOCKTNOSUPPORT"),
+        (10045, "WSAEOPNOTSUPP"),
+        (10046, "WSAEPFNOSUPPORT"),
+        (10047, "WSAEAFNOSUPPORT"),
+        (10048, "WSAEADDRINUSE"),
+        (10049, "WSAEADDRNOTAVAIL"),
+        (10050, "WSAENETDOWN"),
+        (10051, "WSAENETUNREACH"),
+        (10052, "WSAENETRESET"),
+        (10053, "WSAECONNABORTED"),
+        (10054, "WSAECONNRESET"),
+        (10055, "WSAENOBUFS"),
+        (10056, "WSAEISCONN"),
+        (10057, "WSAENOTCONN"),
+        (10058, "WSAESHUTDOWN"),
+        (10059, "WSAETOOMANYREFS"),
+        (10060, "WSAETIMEDOUT"),
+        (10061, "WSAECONNREFUSED"),
+        (10062, "WSAELOOP"),
+        (10063, "WSAENAMETOOLONG"),
+        (10064, "WSAEHOSTDOWN"),
+        (10065, "WSAEHOSTUNREACH"),
+        (10066, "WSAENOTEMPTY"),
+        (10067, "WSAEPROCLIM"),
+        (10068, "WSAEUSERS"),
+        (10069, "WSAEDQUOT"),
+        (10070, "WSAESTALE"),
+        (10071, "WSAEREMOTE"),
+        (10091, "WSASYSNOTREADY"),
+        (10092, "WSAVERNOTSUPPORTED"),
+        (10093, "WSANOTINITIALISED"),
+        (10101, "WSAEDISCON"),
+        (10102, "WSAENOMORE"),
+        (10103, "WSAECANCELLED"),
+        (10104, "WSAEINVALIDPROCTABLE"),
+        (10105, "WSAEINVALIDPROVIDER"),
+        (10106, "WSAEPROVIDERFAILEDINIT"),
+        (10107, "WSASYSCALLFAILURE"),
+        (10108, "WSASERVICE_NOT_FOUND"),
+        (10109, "WSATYPE_NOT_FOUND"),
+        (10110, "WSA_E_NO_MORE"),
+        (10111, "WSA_E_CANCELLED"),
+        (10112, "WSAEREFUSED"),
+        (11001, "WSAHOST_NOT_FOUND"),
+        (11002, "WSATRY_AGAIN"),
+        (11003, "WSANO_RECOVERY"),
+        (11004, "WSANO_DATA"),
+        (11005, "WSA_QOS_RECEIVERS"),
+        (11006, "WSA_QOS_SENDERS"),
+        (11007, "WSA_QOS_NO_SENDERS"),
+        (11008, "WSA_QOS_NO_RECEIVERS"),
+        (11009, "WSA_QOS_REQUEST_CONFIRMED"),
+        (11010, "WSA_QOS_ADMISSION_FAILURE"),
+        (11011, "WSA_QOS_POLICY_FAILURE"),
+        (11012, "WSA_QOS_BAD_STYLE"),
+        (11013, "WSA_QOS_BAD_OBJECT"),
+        (11014, "WSA_QOS_TRAFFIC_CTRL_ERROR"),
+        (11015, "WSA_QOS_GENERIC_ERROR"),
+        (11016, "WSA_QOS_ESERVICETYPE"),
+        (11017, "WSA_QOS_EFLOWSPEC"),
+        (11018, "WSA_QOS_EPROVSPECBUF"),
+        (11019, "WSA_QOS_EFILTERSTYLE"),
+        (11020, "WSA_QOS_EFILTERTYPE"),
+        (11021, "WSA_QOS_EFILTERCOUNT"),
+        (11022, "WSA_QOS_EOBJLENGTH"),
+        (11023, "WSA_QOS_EFLOWCOUNT"),
+        (11024, "WSA_QOS_EUNKOWNPSOBJ"),
+        (11025, "WSA_QOS_EPOLICYOBJ"),
+        (11026, "WSA_QOS_EFLOWDESC"),
+        (11027, "WSA_QOS_EPSFLOWSPEC"),
+        (11028, "WSA_QOS_EPSFILTERSPEC"),
+        (11029, "WSA_QOS_ESDMODEOBJ"),
+        (11030, "WSA_QOS_ESHAPERATEOBJ"),
+        (11031, "WSA_QOS_RESERVED_PETYPE"),
+    ];
+
+    #[test]
+    fn catalogue_round_trips() {
+        for &(code, name) in TABLE {
+            let err = WsaError::from(code);
+            assert_ne!(err, WsaError::Other(code), "{name} is missing");
+            assert_eq!(err.code(), code);
+            assert_eq!(err.name(), Some(name));
+            assert_eq!(i32::from(err), code);
+        }
+    }
+
+    #[test]
+    fn all_matches_table() {
+        let codes: Vec<_> = WsaError::ALL.iter().map(|err| err.code()).collect();
+        let expected: Vec<_> = TABLE.iter().map(|&(code, _)| code).collect();
+        assert_eq!(codes, expected);
+    }
+
+    #[test]
+    fn other_codes_are_kept() {
+        for code in (-1..=12_000).filter(|code| TABLE.iter().all(|&(known, _)| known != *code)) {
+            let err = WsaError::from(code);
+            assert_eq!(err, WsaError::Other(code));
+            assert_eq!(err.code(), code);
+            assert_eq!(err.name(), None);
+        }
+    }
+
+    #[test]
+    fn display_links_to_docs() {
+        let shown = WsaError::SystemNotReady.to_string();
+        assert!(shown.starts_with("WSASYSNOTREADY (10091): The underlying network subsystem"));
+        assert!(!shown.contains('\n'));
+        let shown = format!("{:#}", WsaError::SystemNotReady);
+        assert!(shown.ends_with("windows-sockets-error-codes-2#WSASYSNOTREADY"));
+
+        let shown = WsaError::Other(42).to_string();
+        assert_eq!(shown, "unknown Winsock error (42)");
+    }
+
+    #[test]
+    fn accessors() {
+        let err = WsaError::VersionNotSupported;
+        assert_eq!(err.name(), Some("WSAVERNOTSUPPORTED"));
+        assert_eq!(err.code(), 10092);
+        assert_eq!(
+            err.docs_url(),
+            "https://learn.microsoft.com/en-us/windows/win32/winsock/windows-sockets-error-codes-2#WSAVERNOTSUPPORTED"
+        );
+        assert_eq!(WsaError::Other(42).message(), None);
+    }
+
+    fn every_error() -> impl Iterator<Item = WsaError> {
+        WsaError::ALL.iter().copied().chain([
+            WsaError::Other(0),
+            WsaError::Other(42),
+            WsaError::Other(-1),
+        ])
+    }
+
+    #[test]
+    fn display_golden() {
+        let mut shown = String::new();
+        for err in every_error() {
+            writeln!(shown, "{err}").unwrap();
+        }
+        golden(
+            "tests/golden/display.txt",
+            include_str!("../tests/golden/display.txt"),
+            &shown,
+        );
+    }
+
+    #[test]
+    fn alternate_display_golden() {
+        let mut shown = String::new();
+        for err in every_error() {
+            writeln!(shown, "{err:#}\n").unwrap();
+        }
+        golden(
+            "tests/golden/display_alternate.txt",
+            include_str!("../tests/golden/display_alternate.txt"),
+            &shown,
+        );
+    }
+
+    #[test]
+    fn io_kinds() {
+        let kinds = [
+            (WsaError::Interrupted, ErrorKind::Interrupted),
+            (WsaError::PermissionDenied, ErrorKind::PermissionDenied),
+            (WsaError::InvalidArgument, ErrorKind::InvalidInput),
+            (WsaError::WouldBlock, ErrorKind::WouldBlock),
+            (WsaError::NoBufferSpace, ErrorKind::OutOfMemory),
+            (WsaError::AddressInUse, ErrorKind::AddrInUse),
+            (WsaError::AddressNotAvailable, ErrorKind::AddrNotAvailable),
+            (WsaError::SystemNotReady, ErrorKind::NetworkDown),
+            (WsaError::ConnectionReset, ErrorKind::ConnectionReset),
+            (WsaError::ConnectionRefused, ErrorKind::ConnectionRefused),
+            (WsaError::TimedOut, ErrorKind::TimedOut),
+            (WsaError::VersionNotSupported, ErrorKind::Unsupported),
+            (WsaError::HostNotFound, ErrorKind::NotFound),
+            (WsaError::TasksLimitReached, ErrorKind::Other),
+            (WsaError::Other(42), ErrorKind::Other),
+        ];
+        for (err, kind) in kinds {
+            assert_eq!(err.kind(), kind, "{err:?}");
+            assert_eq!(io::Error::from(err).kind(), kind, "{err:?}");
+        }
+    }
+
+    #[test]
+    fn io_round_trips() {
+        for &err in WsaError::ALL.iter().chain(&[WsaError::Other(-7)]) {
+            let io = io::Error::from(err);
+            assert_eq!(WsaError::try_from(&io).ok(), Some(err));
+            assert_eq!(WsaError::try_from(io).ok(), Some(err));
+        }
+    }
+
+    #[test]
+    fn recognizes_raw_os_errors() {
+        let io = io::Error::from_raw_os_error(10054);
+        assert_eq!(
+            WsaError::try_from(&io).ok(),
+            Some(WsaError::ConnectionReset)
+        );
+
+        let io = io::Error::from_raw_os_error(11031);
+        assert_eq!(
+            WsaError::try_from(io).ok(),
+            Some(WsaError::QosReservedPolicyElement)
+        );
+
+        let io = io::Error::from_raw_os_error(12345);
+        assert!(WsaError::try_from(&io).is_err());
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn ignores_os_errors_shared_with_windows() {
+        let io = io::Error::from_raw_os_error(6);
+        assert!(WsaError::try_from(&io).is_err());
+    }
+
+    #[test]
+    fn gives_back_other_io_errors() {
+        let io = io::Error::new(ErrorKind::NotFound, "not winsock");
+        let back = WsaError::try_from(io).unwrap_err();
+        assert_eq!(back.kind(), ErrorKind::NotFound);
+        assert_eq!(back.to_string(), "not winsock");
+    }
+}
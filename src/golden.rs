@@ -0,0 +1,15 @@
+//! This module compares test output with the golden files checked in next to the tests
+
+use std::{env, fs, path::Path};
+
+/// Compares `shown` with the golden file at `path`, rewriting it instead when `UPDATE_GOLDEN` is set
+pub fn golden(path: &str, golden: &str, shown: &str) {
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(Path::new(env!("CARGO_MANIFEST_DIR")).join(path), shown).unwrap();
+    } else {
+        assert_eq!(
+            shown, golden,
+            "{path} is out of date, rerun with UPDATE_GOLDEN=1"
+        );
+    }
+}
@@ -0,0 +1,178 @@
+//! This module holds [`WsaInfo`], the safe and owned version of the `WSADATA` filled in by `WSAStartup`
+
+use crate::{sys::WSADATA, WsaVersion};
+use std::os::raw::c_char;
+
+/// The details of the Windows Sockets implementation, as reported by `WSAStartup`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WsaInfo {
+    version: WsaVersion,
+    high_version: WsaVersion,
+    description: String,
+    system_status: String,
+    max_sockets: u16,
+    max_udp_datagram: u16,
+}
+
+impl WsaInfo {
+    pub(crate) fn new(
+        version: WsaVersion,
+        high_version: WsaVersion,
+        description: &str,
+        system_status: &str,
+    ) -> Self {
+        Self {
+            version,
+            high_version,
+            description: description.to_owned(),
+            system_status: system_status.to_owned(),
+            max_sockets: 0,
+            max_udp_datagram: 0,
+        }
+    }
+
+    /// The version of Windows Sockets that was negotiated, which the caller is expected to use
+    #[must_use]
+    pub const fn version(&self) -> WsaVersion {
+        self.version
+    }
+
+    /// The highest version of Windows Sockets the implementation supports
+    #[must_use]
+    pub const fn high_version(&self) -> WsaVersion {
+        self.high_version
+    }
+
+    /// A description of the Windows Sockets implementation
+    #[must_use]
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// The status or configuration information of the Windows Sockets implementation
+    #[must_use]
+    pub fn system_status(&self) -> &str {
+        &self.system_status
+    }
+
+    /// The maximum number of sockets that may be opened.\
+    /// Ignored by Windows Sockets 2 and later, which report 0
+    #[must_use]
+    pub const fn max_sockets(&self) -> u16 {
+        self.max_sockets
+    }
+
+    /// The maximum datagram message size.\
+    /// Ignored by Windows Sockets 2 and later, which report 0
+    #[must_use]
+    pub const fn max_udp_datagram(&self) -> u16 {
+        self.max_udp_datagram
+    }
+}
+
+impl From<&WSADATA> for WsaInfo {
+    fn from(data: &WSADATA) -> Self {
+        Self {
+            version: WsaVersion::from_word(data.wVersion),
+            high_version: WsaVersion::from_word(data.wHighVersion),
+            description: decode(&data.szDescription),
+            system_status: decode(&data.szSystemStatus),
+            max_sockets: data.iMaxSockets,
+            max_udp_datagram: data.iMaxUdpDg,
+        }
+    }
+}
+
+/// Decodes a C string out of a fixed size array, which might not be null terminated when full
+fn decode(chars: &[c_char]) -> String {
+    #[allow(clippy::cast_sign_loss)]
+    let bytes: Vec<u8> = chars
+        .iter()
+        .take_while(|&&c| c != 0)
+        .map(|&c| c as u8)
+        .collect();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[cfg(all(test, target_pointer_width = "64"))]
+mod tests {
+    use super::WsaInfo;
+    use crate::{sys::WSADATA, WsaVersion};
+    use std::mem::size_of;
+
+    const DESCRIPTION: usize = 16;
+    const SYSTEM_STATUS: usize = DESCRIPTION + 257;
+
+    /// Builds a `WSADATA` the way it is laid out in memory on 64 bit windows
+    fn data(fill: impl FnOnce(&mut [u8])) -> WSADATA {
+        let mut bytes = vec![0_u8; size_of::<WSADATA>()];
+        fill(&mut bytes);
+        unsafe { bytes.as_ptr().cast::<WSADATA>().read_unaligned() }
+    }
+
+    fn put(bytes: &mut [u8], at: usize, value: &[u8]) {
+        bytes[at..at + value.len()].copy_from_slice(value);
+    }
+
+    #[test]
+    fn layout() {
+        assert_eq!(size_of::<WSADATA>(), 408);
+    }
+
+    #[test]
+    fn decodes_fields() {
+        let info = WsaInfo::from(&data(|bytes| {
+            put(bytes, 0, &[2, 2]);
+            put(bytes, 2, &[2, 2]);
+            put(bytes, 4, &0_u16.to_le_bytes());
+            put(bytes, 6, &0_u16.to_le_bytes());
+            put(bytes, DESCRIPTION, b"WinSock 2.0\0garbage");
+            put(bytes, SYSTEM_STATUS, b"Running\0");
+        }));
+
+        assert_eq!(info.version(), WsaVersion::V2_2);
+        assert_eq!(info.high_version(), WsaVersion::V2_2);
+        assert_eq!(info.description(), "WinSock 2.0");
+        assert_eq!(info.system_status(), "Running");
+        assert_eq!((info.max_sockets(), info.max_udp_datagram()), (0, 0));
+    }
+
+    #[test]
+    fn decodes_legacy_limits() {
+        let info = WsaInfo::from(&data(|bytes| {
+            put(bytes, 0, &[1, 1]);
+            put(bytes, 2, &[2, 2]);
+            put(bytes, 4, &32767_u16.to_le_bytes());
+            put(bytes, 6, &65467_u16.to_le_bytes());
+        }));
+
+        assert_eq!(info.version(), WsaVersion::new(1, 1));
+        assert_eq!(info.high_version(), WsaVersion::V2_2);
+        assert_eq!(
+            (info.max_sockets(), info.max_udp_datagram()),
+            (32767, 65467)
+        );
+        assert_eq!((info.description(), info.system_status()), ("", ""));
+    }
+
+    #[test]
+    fn decodes_unterminated_strings() {
+        let info = WsaInfo::from(&data(|bytes| {
+            put(bytes, DESCRIPTION, &[b'd'; 257]);
+            put(bytes, SYSTEM_STATUS, &[b's'; 129]);
+        }));
+
+        assert_eq!(info.description(), "d".repeat(257));
+        assert_eq!(info.system_status(), "s".repeat(129));
+    }
+
+    #[test]
+    fn decodes_invalid_utf8_lossily() {
+        let info = WsaInfo::from(&data(|bytes| {
+            put(bytes, DESCRIPTION, b"Win\xffSock\0");
+        }));
+
+        assert_eq!(info.description(), "Win\u{fffd}Sock");
+    }
+}
@@ -0,0 +1,96 @@
+//! This module holds wrappers around the calling thread's Winsock error,
+//! for code going past startup and into the socket calls themselves
+
+use crate::{sys, Result, WsaError};
+
+/// The calling thread's last Winsock error, as `WSAGetLastError` reports it,
+/// [`None`] if there is no error
+#[must_use]
+pub fn last_error() -> Option<WsaError> {
+    match unsafe { sys::WSAGetLastError() } {
+        0 => None,
+        code => Some(code.into()),
+    }
+}
+
+/// Sets the calling thread's last Winsock error through `WSASetLastError`,
+/// [`None`] clears it
+pub fn set_last_error(err: Option<WsaError>) {
+    unsafe { sys::WSASetLastError(err.map_or(0, WsaError::code)) }
+}
+
+/// A value returned from a Winsock call, which signals failure with a sentinel value
+pub trait Sentinel: Copy + PartialEq {
+    /// The value the call returns when it fails
+    const FAILED: Self;
+}
+
+/// `SOCKET_ERROR`, returned by most Winsock calls
+impl Sentinel for i32 {
+    const FAILED: Self = sys::SOCKET_ERROR;
+}
+
+/// `INVALID_SOCKET`, returned by the calls creating a `SOCKET`
+impl Sentinel for usize {
+    const FAILED: Self = sys::INVALID_SOCKET;
+}
+
+/// Turns the value returned from a Winsock call into a [`Result`],
+/// erring on the thread's [`last_error`] if the call returned `SOCKET_ERROR` or `INVALID_SOCKET`
+/// # Errors
+/// Returns the thread's [`last_error`] when `returned` is the failure sentinel,
+/// or [`WsaError::Other`] with the sentinel if Winsock didn't set one
+pub fn check<T: Sentinel>(returned: T) -> Result<T> {
+    if returned == T::FAILED {
+        Err(last_error().unwrap_or(WsaError::Other(sys::SOCKET_ERROR)))
+    } else {
+        Ok(returned)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check, last_error, set_last_error, Sentinel};
+    use crate::WsaError;
+    use std::thread;
+
+    #[test]
+    fn set_and_get() {
+        set_last_error(Some(WsaError::WouldBlock));
+        assert_eq!(last_error(), Some(WsaError::WouldBlock));
+        set_last_error(Some(WsaError::Other(12345)));
+        assert_eq!(last_error(), Some(WsaError::Other(12345)));
+        set_last_error(None);
+        assert_eq!(last_error(), None);
+    }
+
+    #[test]
+    fn per_thread() {
+        set_last_error(Some(WsaError::ConnectionReset));
+        thread::spawn(|| {
+            assert_eq!(last_error(), None);
+            set_last_error(Some(WsaError::TimedOut));
+        })
+        .join()
+        .unwrap();
+        assert_eq!(last_error(), Some(WsaError::ConnectionReset));
+    }
+
+    #[test]
+    fn checks_socket_error() {
+        set_last_error(Some(WsaError::NotConnected));
+        assert_eq!(check(0), Ok(0));
+        assert_eq!(check(42), Ok(42));
+        assert_eq!(check(i32::FAILED), Err(WsaError::NotConnected));
+
+        set_last_error(None);
+        assert_eq!(check(-1), Err(WsaError::Other(-1)));
+    }
+
+    #[test]
+    fn checks_invalid_socket() {
+        set_last_error(Some(WsaError::TooManyOpenFiles));
+        assert_eq!(check(3_usize), Ok(3));
+        assert_eq!(check(usize::FAILED), Err(WsaError::TooManyOpenFiles));
+    }
+}
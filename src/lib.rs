@@ -1,149 +1,648 @@
 //! This crate allows you to initialize WSA
+//!
+//! The same API is available on every platform, outside of windows initializing is a no-op that always succeeds
+//!
+//! With the `capi` feature, the `capi` module exports C functions sharing the WSA initialization.
+//! Cargo doesn't build C libraries for it on its own, build one with
+//! `cargo rustc --release --features capi --crate-type cdylib` (or `staticlib`)
 
-#![cfg(windows)]
 #![warn(clippy::pedantic, clippy::nursery, clippy::cargo)]
 
+pub mod backend;
+#[cfg(feature = "capi")]
+pub mod capi;
+mod category;
+mod cleanup;
+#[cfg(feature = "dynamic")]
+mod dynamic;
+mod error;
+#[cfg(test)]
+mod golden;
+mod info;
+mod last_error;
+pub mod lsp;
+pub mod protocol;
+mod retry;
+#[cfg(feature = "serde")]
+pub mod schema;
+mod scoped;
+pub mod shared;
+mod sys;
+mod token;
+mod trace;
+pub mod tracker;
 pub mod util;
+mod version;
+
+pub use backend::{Backend, Simulated, Winsock};
+pub use category::ErrorCategory;
+pub use cleanup::CleanupPolicy;
+#[cfg(feature = "dynamic")]
+pub use dynamic::{Dynamic, LoadError};
+pub use error::WsaError;
+pub use info::WsaInfo;
+pub use last_error::{check, last_error, set_last_error, Sentinel};
+pub use lsp::LspReport;
+pub use protocol::ProtocolInfo;
+pub use retry::{Attempt, Backoff, Clock, RetryPolicy, SimulatedClock, SystemClock};
+pub use scoped::Scoped;
+pub use shared::SharedWsa;
+pub use token::WsaToken;
+pub use version::{ParseVersionError, WsaVersion};
+#[cfg(feature = "macros")]
+pub use wsa_startup_macros::{main, test};
 
-use std::{
-    error::Error,
-    fmt::{Display, Formatter, Result as FmtResult},
-};
-use winapi::{
-    shared::minwindef::MAKEWORD as make_version,
-    um::winsock2::{self as win, WSADATA},
-};
+use scoped::UnwindGuard;
+use std::{panic::Location, time::Duration};
+use tracker::Tracked;
+use WsaError::{NotInitialised, VersionNotSupported};
 
 /// Convenience type alias for a result that errs on [`WsaError`]
 pub type Result<T, E = WsaError> = std::result::Result<T, E>;
 
-/// An Error returned from `WSAStartup`
-#[derive(Debug)]
-pub enum WsaError {
-    SystemNotReady,
-    VersionNotSupported,
-    OperationInProgress,
-    TasksLimitReached,
-    InvalidData,
-    UnknownError,
+/// Initializes `WSA`, calls `WSAStartup` upon initialization, builder for [`Wsa`].\
+/// Generic over the [`Backend`] making the Winsock calls, the real [`Winsock`] by default
+pub struct WsaInitializer<B: Backend = Winsock> {
+    version: WsaVersion,
+    fallbacks: Vec<WsaVersion>,
+    backend: B,
+    cleanup: CleanupPolicy,
+    retry: Option<RetryPolicy>,
 }
 
-use WsaError::{
-    InvalidData, OperationInProgress, SystemNotReady, TasksLimitReached, UnknownError,
-    VersionNotSupported,
-};
-
-impl Error for WsaError {}
-
-impl Display for WsaError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-        const ERR_CODES: &str =
-            "https://docs.microsoft.com/en-us/windows/win32/winsock/windows-sockets-error-codes-2";
-        const GENERAL: &str =
-            "https://docs.microsoft.com/en-us/windows/win32/api/winsock/nf-winsock-wsastartup";
-
-        match self {
-            UnknownError => write!(f, "Some unknown error has occurred, it's time to panic.\nsee \"{}\" ", GENERAL)?,
-            SystemNotReady => write!(f, "The underlying network subsystem is not ready for network communication.\nsee \"{}/#WSASYSNOTREADY\" ", ERR_CODES)?,
-            VersionNotSupported => write!(f, "The version of Windows Sockets support requested is not provided by this particular Windows Sockets implementation.\nsee \"{}/#WSAVERNOTSUPPORTED\" ", ERR_CODES)?,
-            OperationInProgress => write!(f, "A blocking Windows Sockets 1.1 operation is in progress.\nsee \"{}/#WSAEINPROGRESS\" ", ERR_CODES)?,
-            TasksLimitReached => write!(f, "A limit on the number of tasks supported by the Windows Sockets implementation has been reached.\nsee \"{}/#WSAEPROCLIM\" ", ERR_CODES)?,
-            InvalidData => write!(f, "The lpWSAData parameter is not a valid pointer.\nsee \"{}/#WSAEFAULT\" ", ERR_CODES)?,
-        }
+/// Control flow, makes sure you clean up `WSA` when you finnish using it
+#[must_use = "You should clean up after yourself, see `.raii` and `.clean`"]
+pub struct Wsa<B: Backend = Winsock> {
+    info: WsaInfo,
+    backend: B,
+    cleanup: CleanupPolicy,
+    tracked: Tracked,
+}
 
-        writeln!(f, "for more information")
-    }
+/// Calls `WSACleanup` on drop, handling failures according to its [`CleanupPolicy`]
+pub struct WsaRaii<B: Backend = Winsock> {
+    info: WsaInfo,
+    backend: B,
+    cleanup: CleanupPolicy,
+    tracked: Tracked,
 }
 
-impl From<i32> for WsaError {
-    fn from(err_code: i32) -> Self {
-        match err_code {
-            10091 => SystemNotReady,
-            10092 => VersionNotSupported,
-            10036 => OperationInProgress,
-            10067 => TasksLimitReached,
-            10014 => InvalidData,
-            _ => UnknownError,
-        }
+impl Default for WsaInitializer {
+    fn default() -> Self {
+        Self::with_backend(Winsock)
     }
 }
 
-/// Initializes `WSA`, calls `WSAStartup` upon initialization, builder for the [`Wsa`] unit struct
-pub struct WsaInitializer {
-    version: u16,
-    data: WSADATA,
-}
+impl<B: Backend> WsaInitializer<B> {
+    /// An initializer making its Winsock calls through `backend`, with version 2.2
+    pub const fn with_backend(backend: B) -> Self {
+        Self {
+            version: WsaVersion::V2_2,
+            fallbacks: Vec::new(),
+            backend,
+            cleanup: CleanupPolicy::Ignore,
+            retry: None,
+        }
+    }
 
-/// Control flow, makes sure you clean up `WSA` when you finnish using it
-#[must_use = "You should clean up after yourself, see `.raii` and `.clean`"]
-pub struct Wsa(());
+    /// Sets the version for WSA to be initialized with,
+    /// either a [`WsaVersion`] or a `u16` in the format `WSAStartup` takes
+    pub fn version(&mut self, new: impl Into<WsaVersion>) -> &mut Self {
+        self.version = new.into();
+        self
+    }
 
-/// Calls `WSACleanup` on drop
-pub struct WsaRaii(());
+    /// Sets the versions to fall back to, in order, when the preferred [`version`](Self::version)
+    /// isn't supported.\
+    /// Once fallbacks are set, a startup is only accepted if it negotiated one of the listed versions,
+    /// otherwise WSA is cleaned up and the next version is tried
+    pub fn fallbacks<V: Into<WsaVersion>>(
+        &mut self,
+        versions: impl IntoIterator<Item = V>,
+    ) -> &mut Self {
+        self.fallbacks = versions.into_iter().map(Into::into).collect();
+        self
+    }
 
-impl Default for WsaInitializer {
-    fn default() -> Self {
-        Self {
-            version: make_version(2, 2),
-            data: unsafe { std::mem::zeroed() },
-        }
+    /// Sets what the [`WsaRaii`] does when `WSACleanup` fails, [`CleanupPolicy::Ignore`] by default
+    pub fn cleanup_policy(&mut self, policy: CleanupPolicy) -> &mut Self {
+        self.cleanup = policy;
+        self
     }
-}
 
-impl WsaInitializer {
-    /// Sets the version for WSA to be initialized with
-    pub fn version(&mut self, new: u16) -> &mut Self {
-        self.version = new;
+    /// Sets how to retry failed startups, they aren't retried by default
+    pub fn retry_policy(&mut self, policy: RetryPolicy) -> &mut Self {
+        self.retry = Some(policy);
         self
     }
 
-    /// Sets the data to be given when WSA is initialized
-    pub fn data(&mut self, new: WSADATA) -> &mut Self {
-        self.data = new;
+    /// Used to set the data to be given when WSA is initialized, has no effect.\
+    /// Takes `winapi`'s `WSADATA` as it always did, whichever other bindings are enabled
+    #[cfg(all(windows, feature = "winapi"))]
+    #[deprecated(note = "`WSADATA` is only written by `WSAStartup`, read it through `Wsa::info`")]
+    #[allow(clippy::missing_const_for_fn)]
+    pub fn data(&mut self, new: winapi::um::winsock2::WSADATA) -> &mut Self {
+        let _ = new;
         self
     }
 
-    /// Initializes WSA by calling `WSAStartup`
+    /// Initializes WSA by calling `WSAStartup`, the returned [`Wsa`] holds the [`WsaInfo`] it reported.\
+    /// When [fallbacks](Self::fallbacks) are set, each version is tried in order until one is negotiated,
+    /// [`WsaInfo::version`] tells which one and [`WsaInfo::high_version`] what the implementation offers.\
+    /// When a [retry policy](Self::retry_policy) is set, failures it deems retryable are retried
     /// # Errors
-    /// Returns a [`WsaError`] if the the initialization fails
-    pub fn init(mut self) -> Result<Wsa> {
-        // WSAStartup(u16, *mut WSADATA) -> i32, reminder: UnsafeCell
-        let result = unsafe { win::WSAStartup(self.version, &mut self.data as *mut _) };
-        if result == 0 {
-            Ok(Wsa(()))
+    /// Returns a [`WsaError`] if the the initialization fails,
+    /// [`WsaError::VersionNotSupported`] if none of the versions could be negotiated
+    #[track_caller]
+    pub fn init(self) -> Result<Wsa<B>>
+    where
+        B: Clone,
+    {
+        self.init_reporting().0
+    }
+
+    /// Initializes WSA like [`init`](Self::init), along with every [`Attempt`] made
+    #[track_caller]
+    pub fn init_reporting(self) -> (Result<Wsa<B>>, Vec<Attempt>)
+    where
+        B: Clone,
+    {
+        let location = Location::caller();
+        let mut attempts = Vec::new();
+        let result = if let Some(policy) = &self.retry {
+            policy.run(&mut attempts, || self.negotiate(location))
         } else {
-            Err(result.into())
+            // A single attempt needs neither the clock nor the jitter of a policy
+            let result = self.negotiate(location);
+            attempts.push(Attempt {
+                number: 1,
+                at: Duration::ZERO,
+                error: result.as_ref().err().copied(),
+                delay: None,
+            });
+            result
+        };
+        (result, attempts)
+    }
+
+    /// Starts WSA up once, trying the version and then the fallbacks
+    fn negotiate(&self, location: &'static Location<'static>) -> Result<Wsa<B>>
+    where
+        B: Clone,
+    {
+        let startup = trace::Startup::enter(self.version);
+        let candidates = std::iter::once(self.version).chain(self.fallbacks.iter().copied());
+        for candidate in candidates {
+            let info = match self.backend.startup(candidate) {
+                Ok(info) => info,
+                Err(err) => {
+                    startup.failed(candidate, err);
+                    if err == VersionNotSupported {
+                        continue;
+                    }
+                    return Err(err);
+                }
+            };
+            let wsa = Wsa {
+                info,
+                backend: self.backend.clone(),
+                cleanup: self.cleanup.clone(),
+                tracked: Tracked::new(location),
+            };
+            if self.accepts(wsa.info.version()) {
+                startup.succeeded(&wsa.info);
+                return Ok(wsa);
+            }
+            startup.rejected(candidate, wsa.info.version());
+            wsa.clean();
         }
+        Err(VersionNotSupported)
     }
+
+    /// Initializes WSA, runs `f` with the [`WsaInfo`] it reported, then cleans WSA up.\
+    /// WSA is cleaned up even if `f` panics, the failure is then handled according to the
+    /// [`CleanupPolicy`]. Otherwise the failure is returned along with the value of `f`
+    /// # Errors
+    /// Returns a [`WsaError`] if the initialization fails, `f` isn't called then
+    #[track_caller]
+    pub fn with_wsa<R>(self, f: impl FnOnce(&WsaInfo) -> R) -> Result<Scoped<R>>
+    where
+        B: Clone,
+    {
+        let mut guard = UnwindGuard(None);
+        let value = f(guard.0.insert(self.init()?).info());
+        let cleanup = guard.0.take().map_or(Ok(()), Wsa::try_clean);
+        Ok(Scoped { value, cleanup })
+    }
+
+    fn accepts(&self, negotiated: WsaVersion) -> bool {
+        self.fallbacks.is_empty()
+            || self.version == negotiated
+            || self.fallbacks.contains(&negotiated)
+    }
+}
+
+impl<B: Backend + Clone + Send + 'static> WsaInitializer<B> {
+    /// Acquires a [`SharedWsa`] handle, WSA is only initialized with these options if no other handle
+    /// is currently alive, the last handle then cleans it up through the same backend
+    /// # Errors
+    /// Returns a [`WsaError`] if this is the first handle and the initialization fails
+    pub fn shared(self) -> Result<SharedWsa> {
+        SharedWsa::acquire_with(self)
+    }
+}
+
+/// Implements the accessors [`Wsa`] and [`WsaRaii`] share once for both, so they can't drift apart
+macro_rules! accessors {
+    ($($guard:ident),*) => {$(
+        impl<B: Backend> $guard<B> {
+            /// The details of the Windows Sockets implementation `WSAStartup` reported
+            #[must_use]
+            pub const fn info(&self) -> &WsaInfo {
+                &self.info
+            }
+
+            /// Every protocol installed in the Winsock catalog, as `WSAEnumProtocolsW` reports them
+            /// # Errors
+            /// Returns the [`WsaError`] `WSAEnumProtocolsW` failed with
+            pub fn protocols(&self) -> Result<Vec<ProtocolInfo>> {
+                self.backend.protocols()
+            }
+
+            /// The Layered Service Providers installed over the protocols in the Winsock catalog
+            /// # Errors
+            /// Returns the [`WsaError`] `WSAEnumProtocolsW` failed with
+            pub fn lsp_report(&self) -> Result<LspReport> {
+                self.protocols()
+                    .map(|catalog| LspReport::from_catalog(&catalog))
+            }
+
+            /// A proof WSA stays initialized for as long as it is borrowed, see [`WsaToken`]
+            #[must_use]
+            pub const fn token(&self) -> WsaToken<'_> {
+                WsaToken::new()
+            }
+        }
+    )*};
 }
 
-impl Wsa {
-    /// Cleans up WSA on drop.  
+accessors!(Wsa, WsaRaii);
+
+impl<B: Backend> Wsa<B> {
+    /// Cleans up WSA on drop.\
     /// Takes ownership of self to assert WSA was initialized and to avoid double cleanup.
-    #[allow(clippy::must_use_candidate, clippy::unused_self)]
-    pub const fn raii(self) -> WsaRaii {
-        WsaRaii(())
+    #[allow(clippy::must_use_candidate)]
+    pub fn raii(self) -> WsaRaii<B> {
+        WsaRaii {
+            info: self.info,
+            backend: self.backend,
+            cleanup: self.cleanup,
+            tracked: self.tracked.raii(),
+        }
     }
 
-    /// cleans WSA.  
+    /// cleans WSA, handling failures according to its [`CleanupPolicy`].\
     /// Takes self to assert WSA was initialized and to avoid double cleanup.
     #[allow(clippy::missing_const_for_fn)]
     pub fn clean(self) {
         self.raii();
     }
+
+    /// cleans WSA, returning the failure instead of handling it.\
+    /// Takes self to assert WSA was initialized and to avoid double cleanup.
+    /// # Errors
+    /// Returns the [`WsaError`] `WSACleanup` failed with,
+    /// such as [`WsaError::NotInitialised`], [`WsaError::OperationInProgress`] or [`WsaError::NetworkDown`]
+    pub fn try_clean(self) -> Result<()> {
+        let result = trace::cleanup(self.backend.cleanup());
+        if result == Err(NotInitialised) {
+            self.tracked.unbalanced();
+        }
+        self.tracked.release();
+        result
+    }
+
+    /// Forgets the initialization without cleaning it up, for when the cleanup is handled elsewhere
+    pub(crate) fn forget(self) {
+        self.tracked.release();
+    }
 }
 
-impl Drop for WsaRaii {
+impl<B: Backend> WsaRaii<B> {
+    /// Sets what to do when `WSACleanup` fails on drop
+    pub fn set_cleanup_policy(&mut self, policy: CleanupPolicy) {
+        self.cleanup = policy;
+    }
+}
+
+impl<B: Backend> Drop for WsaRaii<B> {
     fn drop(&mut self) {
-        // TODO: Find a way to use result
-        let _ = unsafe { win::WSACleanup() };
+        if let Err(err) = trace::cleanup(self.backend.cleanup()) {
+            if err == NotInitialised {
+                self.tracked.unbalanced();
+            }
+            self.cleanup.handle(err);
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::{
+        lsp::RiskLevel, sys, Backend, Backoff, CleanupPolicy, ProtocolInfo, Result, RetryPolicy,
+        Simulated, SimulatedClock, Winsock, WsaError, WsaInitializer, WsaVersion,
+    };
+    use std::{
+        panic::{self, AssertUnwindSafe},
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
     #[test]
     fn it_works() {
         assert_eq!(2 + 2, 4);
     }
+
+    #[test]
+    fn init_reports_info() {
+        let simulated = Simulated::new();
+        let wsa = WsaInitializer::with_backend(simulated.clone())
+            .init()
+            .unwrap();
+        assert_eq!(wsa.info().version(), WsaVersion::V2_2);
+        assert_eq!(wsa.info().description(), "Simulated WinSock");
+
+        let raii = wsa.raii();
+        assert_eq!(raii.info().system_status(), "Running");
+        assert_eq!(simulated.startups(), 1);
+        drop(raii);
+        assert_eq!(simulated.startups(), 0);
+    }
+
+    #[test]
+    fn init_fails_with_backend_error() {
+        let simulated = Simulated::new();
+        simulated.fail_startup(WsaError::SystemNotReady);
+        let result = WsaInitializer::with_backend(simulated.clone()).init();
+        assert_eq!(result.err(), Some(WsaError::SystemNotReady));
+        assert_eq!(simulated.startups(), 0);
+    }
+
+    #[test]
+    fn negotiates_down_the_fallbacks() {
+        let simulated = Simulated::new();
+        let mut initializer = WsaInitializer::with_backend(simulated.clone());
+        initializer
+            .version(WsaVersion::new(3, 0))
+            .fallbacks([(0, 9), (2, 0), (1, 1)]);
+        let wsa = initializer.init().unwrap();
+        assert_eq!(wsa.info().version(), WsaVersion::new(2, 0));
+        assert_eq!(wsa.info().high_version(), WsaVersion::V2_2);
+        assert_eq!(simulated.startups(), 1);
+        wsa.clean();
+        assert_eq!(simulated.startups(), 0);
+    }
+
+    #[test]
+    fn no_acceptable_version() {
+        let simulated = Simulated::new();
+        let mut initializer = WsaInitializer::with_backend(simulated.clone());
+        initializer.version((0, 5)).fallbacks([(3, 0), (0, 9)]);
+        assert_eq!(
+            initializer.init().err(),
+            Some(WsaError::VersionNotSupported)
+        );
+        assert_eq!(simulated.startups(), 0);
+    }
+
+    #[test]
+    fn without_fallbacks_accepts_any_negotiated() {
+        let simulated = Simulated::supporting(WsaVersion::new(1, 0), WsaVersion::new(2, 0));
+        let wsa = WsaInitializer::with_backend(simulated).init().unwrap();
+        assert_eq!(wsa.info().version(), WsaVersion::new(2, 0));
+        wsa.clean();
+    }
+
+    /// A version to request and the versions to fall back to
+    type Case = ((u8, u8), &'static [(u8, u8)]);
+
+    fn negotiate<B: Backend + Clone>(
+        backend: B,
+        version: (u8, u8),
+        fallbacks: &[(u8, u8)],
+    ) -> Result<(WsaVersion, WsaVersion)> {
+        let mut initializer = WsaInitializer::with_backend(backend);
+        initializer
+            .version(version)
+            .fallbacks(fallbacks.iter().copied());
+        let wsa = initializer.init()?;
+        let negotiated = (wsa.info().version(), wsa.info().high_version());
+        wsa.clean();
+        Ok(negotiated)
+    }
+
+    #[test]
+    fn winsock_matches_simulated() {
+        let _serial = sys::serial();
+        let cases: &[Case] = &[
+            ((2, 2), &[]),
+            ((1, 1), &[]),
+            ((3, 0), &[]),
+            ((3, 0), &[(2, 0)]),
+            ((3, 0), &[(2, 1), (1, 1)]),
+            ((2, 2), &[(1, 1)]),
+            ((0, 9), &[]),
+            ((0, 9), &[(1, 1)]),
+        ];
+        for &(version, fallbacks) in cases {
+            let simulated = Simulated::new();
+            assert_eq!(
+                negotiate(Winsock, version, fallbacks),
+                negotiate(simulated.clone(), version, fallbacks),
+                "requesting {version:?} falling back to {fallbacks:?}"
+            );
+            assert_eq!(simulated.startups(), 0);
+        }
+        assert_eq!(Winsock.last_error(), None);
+    }
+
+    #[test]
+    fn try_clean_reports_failures() {
+        let simulated = Simulated::new();
+        let wsa = WsaInitializer::with_backend(simulated.clone())
+            .init()
+            .unwrap();
+        simulated.fail_cleanup(WsaError::NetworkDown);
+        assert_eq!(wsa.try_clean(), Err(WsaError::NetworkDown));
+
+        let wsa = WsaInitializer::with_backend(simulated.clone())
+            .init()
+            .unwrap();
+        assert_eq!(wsa.try_clean(), Ok(()));
+        assert_eq!(simulated.startups(), 1);
+    }
+
+    #[test]
+    fn try_clean_detects_unbalanced_cleanup() {
+        let simulated = Simulated::new();
+        let wsa = WsaInitializer::with_backend(simulated.clone())
+            .init()
+            .unwrap();
+        simulated.cleanup().unwrap();
+        assert_eq!(wsa.try_clean(), Err(WsaError::NotInitialised));
+    }
+
+    #[test]
+    fn drop_hands_failures_to_callback() {
+        let failures = Arc::new(Mutex::new(Vec::new()));
+        let simulated = Simulated::new();
+        let mut initializer = WsaInitializer::with_backend(simulated.clone());
+        initializer.cleanup_policy(CleanupPolicy::callback({
+            let failures = Arc::clone(&failures);
+            move |err| failures.lock().unwrap().push(err)
+        }));
+
+        let raii = initializer.init().unwrap().raii();
+        simulated.fail_cleanup(WsaError::OperationInProgress);
+        drop(raii);
+        assert_eq!(*failures.lock().unwrap(), [WsaError::OperationInProgress]);
+
+        let wsa = WsaInitializer::with_backend(simulated).init().unwrap();
+        wsa.clean();
+        assert_eq!(failures.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn drop_ignores_failures_by_default() {
+        let simulated = Simulated::new();
+        let wsa = WsaInitializer::with_backend(simulated.clone())
+            .init()
+            .unwrap();
+        simulated.fail_cleanup(WsaError::NetworkDown);
+        wsa.clean();
+    }
+
+    #[test]
+    fn retries_transient_startup_failures() {
+        let simulated = Simulated::new();
+        simulated
+            .fail_startup(WsaError::SystemNotReady)
+            .fail_startup(WsaError::TasksLimitReached);
+        let clock = SimulatedClock::new();
+        let mut initializer = WsaInitializer::with_backend(simulated.clone());
+        initializer.retry_policy(
+            RetryPolicy::new()
+                .backoff(Backoff::Fixed(Duration::from_secs(1)))
+                .clock(clock.clone()),
+        );
+        let (wsa, attempts) = initializer.init_reporting();
+        let errors: Vec<_> = attempts.iter().map(|attempt| attempt.error).collect();
+        assert_eq!(
+            errors,
+            [
+                Some(WsaError::SystemNotReady),
+                Some(WsaError::TasksLimitReached),
+                None
+            ]
+        );
+        assert_eq!(clock.sleeps(), [Duration::from_secs(1); 2]);
+        assert_eq!(simulated.startups(), 1);
+        wsa.unwrap().clean();
+    }
+
+    #[test]
+    fn no_retries_by_default() {
+        let simulated = Simulated::new();
+        simulated.fail_startup(WsaError::SystemNotReady);
+        let (wsa, attempts) = WsaInitializer::with_backend(simulated).init_reporting();
+        assert_eq!(wsa.err(), Some(WsaError::SystemNotReady));
+        assert_eq!(attempts.len(), 1);
+    }
+
+    #[test]
+    fn with_wsa_cleans_up() {
+        let simulated = Simulated::new();
+        let scoped = WsaInitializer::with_backend(simulated.clone())
+            .with_wsa(|info| (info.version(), simulated.startups()))
+            .unwrap();
+        assert_eq!(scoped.into_result(), Ok((WsaVersion::V2_2, 1)));
+        assert_eq!(simulated.startups(), 0);
+    }
+
+    #[test]
+    fn with_wsa_keeps_value_on_cleanup_failure() {
+        let simulated = Simulated::new();
+        let scoped = WsaInitializer::with_backend(simulated.clone())
+            .with_wsa(|_| simulated.fail_cleanup(WsaError::NetworkDown).startups())
+            .unwrap();
+        assert_eq!(scoped.value, 1);
+        assert_eq!(scoped.cleanup, Err(WsaError::NetworkDown));
+        assert_eq!(scoped.into_result(), Err(WsaError::NetworkDown));
+    }
+
+    #[test]
+    fn with_wsa_skips_closure_on_startup_failure() {
+        let simulated = Simulated::new();
+        simulated.fail_startup(WsaError::SystemNotReady);
+        let result = WsaInitializer::with_backend(simulated).with_wsa(|_| unreachable!());
+        assert_eq!(result.err(), Some(WsaError::SystemNotReady));
+    }
+
+    #[test]
+    fn with_wsa_cleans_up_on_unwind() {
+        let failures = Arc::new(Mutex::new(Vec::new()));
+        let simulated = Simulated::new();
+        let mut initializer = WsaInitializer::with_backend(simulated.clone());
+        initializer.cleanup_policy(CleanupPolicy::callback({
+            let failures = Arc::clone(&failures);
+            move |err| failures.lock().unwrap().push(err)
+        }));
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            initializer.with_wsa(|_| -> () { panic!("in the closure") })
+        }));
+        assert!(result.is_err());
+        assert_eq!(simulated.startups(), 0);
+        assert!(failures.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn lists_protocols() {
+        let catalog =
+            ProtocolInfo::decode_catalog(include_bytes!("../tests/fixtures/catalog/windows10.bin"))
+                .unwrap();
+        let simulated = Simulated::new();
+        simulated.set_protocols(catalog.clone());
+        assert_eq!(simulated.protocols(), Err(WsaError::NotInitialised));
+
+        let wsa = WsaInitializer::with_backend(simulated.clone())
+            .init()
+            .unwrap();
+        assert_eq!(wsa.protocols(), Ok(catalog.clone()));
+        assert_eq!(
+            wsa.lsp_report().map(|report| report.risk),
+            Ok(RiskLevel::None)
+        );
+        let raii = wsa.raii();
+        assert_eq!(raii.protocols(), Ok(catalog));
+        drop(raii);
+        assert_eq!(simulated.protocols(), Err(WsaError::NotInitialised));
+
+        // The no-op Winsock has an empty catalog
+        #[cfg(not(windows))]
+        assert_eq!(Winsock.protocols(), Ok(Vec::new()));
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "failed to clean up WSA")]
+    fn drop_panics_in_debug() {
+        let simulated = Simulated::new();
+        let mut raii = WsaInitializer::with_backend(simulated.clone())
+            .init()
+            .unwrap()
+            .raii();
+        raii.set_cleanup_policy(CleanupPolicy::PanicInDebug);
+        simulated.fail_cleanup(WsaError::NotInitialised);
+        drop(raii);
+    }
 }
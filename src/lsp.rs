@@ -0,0 +1,488 @@
+//! This module holds [`LspReport`], which finds the Layered Service Providers in the Winsock catalog.
+//!
+//! Antivirus and VPN software layer themselves over the base protocols this way,
+//! and a misbehaving layer is a common reason for raw sockets failing.
+
+use crate::{
+    protocol::{Guid, ProtocolChain},
+    ProtocolInfo,
+};
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+
+/// The socket type of raw sockets, `SOCK_RAW`
+const SOCK_RAW: i32 = 3;
+
+/// A provider of layered protocols, which isn't a base provider
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LayeredProvider {
+    /// The GUID of the provider
+    pub id: Guid,
+    /// The name of its layered protocol entry, or of its first chain without one
+    pub name: String,
+    /// Every catalog entry the provider installed, layered and chained
+    pub entries: Vec<u32>,
+}
+
+/// A protocol chain, which routes the sockets of a base protocol through layered ones
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LayeredChain {
+    /// The catalog entry of the chain
+    pub entry: u32,
+    /// The name of the chain
+    pub name: String,
+    /// The GUID of the provider that installed the chain, the one of its top layer's entry.\
+    /// When that entry isn't in the catalog, the chain's own GUID
+    pub provider: Guid,
+    /// The catalog entries of the layers, from the top one down
+    pub layers: Vec<u32>,
+    /// The catalog entry of the base protocol the chain ends at
+    pub base: u32,
+    /// The entries the chain refers to which aren't in the catalog, making it broken
+    pub missing: Vec<u32>,
+}
+
+/// A base protocol which chains are layered over
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct InterceptedProtocol {
+    /// The catalog entry of the base protocol
+    pub entry: u32,
+    /// The name of the base protocol
+    pub name: String,
+    /// The address family of its sockets
+    pub address_family: i32,
+    /// The type of its sockets
+    pub socket_type: i32,
+    /// The protocol of its sockets
+    pub protocol: i32,
+    /// The providers whose chains end at it
+    pub providers: Vec<Guid>,
+}
+
+/// How likely the layered providers are to break sockets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum RiskLevel {
+    /// No layered providers are installed
+    None,
+    /// Layered providers are installed, but don't intercept any protocol
+    Low,
+    /// Layered providers intercept stream or datagram protocols
+    Medium,
+    /// Layered providers intercept raw sockets, or a chain is broken
+    High,
+}
+
+/// Something found in the catalog that may break sockets
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Finding {
+    /// How likely it is to break sockets
+    pub level: RiskLevel,
+    /// What was found
+    pub message: String,
+}
+
+/// The Layered Service Providers installed in a Winsock catalog, see [`LspReport::from_catalog`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LspReport {
+    /// The providers of layered protocols, in catalog order
+    pub providers: Vec<LayeredProvider>,
+    /// The protocol chains, in catalog order
+    pub chains: Vec<LayeredChain>,
+    /// The base protocols chains are layered over, in catalog order
+    pub intercepted: Vec<InterceptedProtocol>,
+    /// The highest level of the findings, [`RiskLevel::None`] if there are none
+    pub risk: RiskLevel,
+    /// What may break sockets, most likely first
+    pub findings: Vec<Finding>,
+}
+
+impl LspReport {
+    /// Finds the layered providers and chains in `catalog`, as listed by
+    /// [`Wsa::protocols`](crate::Wsa::protocols)
+    #[must_use]
+    pub fn from_catalog(catalog: &[ProtocolInfo]) -> Self {
+        let by_id: HashMap<_, _> = catalog
+            .iter()
+            .map(|entry| (entry.catalog_entry_id(), entry))
+            .collect();
+
+        let mut providers: Vec<LayeredProvider> = Vec::new();
+        let mut chains = Vec::new();
+        for entry in catalog {
+            if *entry.chain() == ProtocolChain::Base {
+                continue;
+            }
+            // A chain belongs to the provider of its top layer, which installers often give a GUID
+            // of its own. Without that layered entry, all there is to go by is the chain's GUID
+            let owner = entry
+                .chain()
+                .entries()
+                .first()
+                .and_then(|top| by_id.get(top))
+                .filter(|top| *top.chain() == ProtocolChain::Layered)
+                .map_or(entry, |top| *top);
+            let id = owner.provider_id();
+            if let Some(provider) = providers.iter_mut().find(|provider| provider.id == id) {
+                provider.entries.push(entry.catalog_entry_id());
+                if *owner.chain() == ProtocolChain::Layered {
+                    owner.name().clone_into(&mut provider.name);
+                }
+            } else {
+                providers.push(LayeredProvider {
+                    id,
+                    name: owner.name().to_owned(),
+                    entries: vec![entry.catalog_entry_id()],
+                });
+            }
+            if let Some((&base, layers)) = entry.chain().entries().split_last() {
+                chains.push(LayeredChain {
+                    entry: entry.catalog_entry_id(),
+                    name: entry.name().to_owned(),
+                    provider: id,
+                    layers: layers.to_vec(),
+                    base,
+                    missing: entry
+                        .chain()
+                        .entries()
+                        .iter()
+                        .copied()
+                        .filter(|id| !by_id.contains_key(id))
+                        .collect(),
+                });
+            }
+        }
+
+        let mut intercepted = Vec::new();
+        for entry in catalog {
+            let id = entry.catalog_entry_id();
+            let mut by = Vec::new();
+            for chain in chains.iter().filter(|chain| chain.base == id) {
+                if !by.contains(&chain.provider) {
+                    by.push(chain.provider);
+                }
+            }
+            if !by.is_empty() && *entry.chain() == ProtocolChain::Base {
+                intercepted.push(InterceptedProtocol {
+                    entry: id,
+                    name: entry.name().to_owned(),
+                    address_family: entry.address_family(),
+                    socket_type: entry.socket_type(),
+                    protocol: entry.protocol(),
+                    providers: by,
+                });
+            }
+        }
+
+        let findings = findings(&providers, &chains, &intercepted);
+        Self {
+            risk: findings
+                .first()
+                .map_or(RiskLevel::None, |finding| finding.level),
+            providers,
+            chains,
+            intercepted,
+            findings,
+        }
+    }
+}
+
+/// What may break sockets, most likely first
+fn findings(
+    providers: &[LayeredProvider],
+    chains: &[LayeredChain],
+    intercepted: &[InterceptedProtocol],
+) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for chain in chains.iter().filter(|chain| !chain.missing.is_empty()) {
+        findings.push(Finding {
+            level: RiskLevel::High,
+            message: format!(
+                "chain {} ({}) refers to entries {:?}, which aren't in the catalog",
+                chain.entry, chain.name, chain.missing
+            ),
+        });
+    }
+    for protocol in intercepted {
+        let (level, effect) = if protocol.socket_type == SOCK_RAW {
+            (
+                RiskLevel::High,
+                "raw sockets may fail to open or see no traffic",
+            )
+        } else {
+            (RiskLevel::Medium, "its sockets go through the layers")
+        };
+        findings.push(Finding {
+            level,
+            message: format!(
+                "{} ({}) is intercepted by {}, {effect}",
+                protocol.name,
+                protocol.entry,
+                names(providers, &protocol.providers)
+            ),
+        });
+    }
+    for provider in providers {
+        if !chains.iter().any(|chain| chain.provider == provider.id) {
+            findings.push(Finding {
+                level: RiskLevel::Low,
+                message: format!(
+                    "{} {} is installed without intercepting any protocol",
+                    provider.name, provider.id
+                ),
+            });
+        }
+    }
+    // Stable, so findings of the same level stay in catalog order
+    findings.sort_by_key(|finding| std::cmp::Reverse(finding.level));
+    findings
+}
+
+/// The names of the providers with the GUIDs `ids`
+fn names(providers: &[LayeredProvider], ids: &[Guid]) -> String {
+    let names: Vec<_> = ids
+        .iter()
+        .map(|id| {
+            providers
+                .iter()
+                .find(|provider| provider.id == *id)
+                .map_or_else(|| id.to_string(), |provider| provider.name.clone())
+        })
+        .collect();
+    names.join(", ")
+}
+
+impl Display for RiskLevel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str(match self {
+            Self::None => "none",
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        })
+    }
+}
+
+/// A plain text report, for pasting into support tickets
+impl Display for LspReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        writeln!(f, "risk: {}", self.risk)?;
+        for finding in &self.findings {
+            writeln!(f, "- [{}] {}", finding.level, finding.message)?;
+        }
+        writeln!(f, "layered providers: {}", self.providers.len())?;
+        for provider in &self.providers {
+            writeln!(
+                f,
+                "- {} {}, entries {:?}",
+                provider.name, provider.id, provider.entries
+            )?;
+        }
+        writeln!(f, "chains: {}", self.chains.len())?;
+        for chain in &self.chains {
+            writeln!(
+                f,
+                "- {} ({}): layers {:?} over {}",
+                chain.name, chain.entry, chain.layers, chain.base
+            )?;
+        }
+        writeln!(f, "intercepted base protocols: {}", self.intercepted.len())?;
+        for protocol in &self.intercepted {
+            writeln!(
+                f,
+                "- {} ({}): family {}, type {}, protocol {}",
+                protocol.name,
+                protocol.entry,
+                protocol.address_family,
+                protocol.socket_type,
+                protocol.protocol
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LspReport, RiskLevel};
+    use crate::{golden::golden, protocol::Guid, ProtocolInfo};
+
+    /// The catalog of a stock Windows 10
+    const WINDOWS_10: &[u8] = include_bytes!("../tests/fixtures/catalog/windows10.bin");
+    /// The same catalog with an LSP layered over IPv4 TCP, UDP and raw sockets,
+    /// its chains first like LSP installers order them
+    const LSP: &[u8] = include_bytes!("../tests/fixtures/catalog/lsp.bin");
+    /// The same LSP with its chains installed under a GUID of their own, as `instlsp` does
+    const CHAIN_GUID: &[u8] = include_bytes!("../tests/fixtures/catalog/lsp_chain_guid.bin");
+
+    const ACME: Guid = Guid::new(
+        0x6D9B_3C2E,
+        0x41F7,
+        0x4A0C,
+        [0x9E, 0x15, 0x7B, 0x2D, 0x8F, 0x3A, 0x5C, 0x61],
+    );
+    /// The GUID of the chains in [`CHAIN_GUID`]
+    const ACME_CHAINS: Guid = Guid::new(
+        0xB3A5_E1C4,
+        0x7D2F,
+        0x4E86,
+        [0xA1, 0xC9, 0x5F, 0x0D, 0x3E, 0x7B, 0x2A, 0x48],
+    );
+
+    /// The catalog in `bytes`, without the entries `removed`
+    fn catalog(bytes: &[u8], removed: &[u32]) -> Vec<ProtocolInfo> {
+        let mut catalog = ProtocolInfo::decode_catalog(bytes).unwrap();
+        catalog.retain(|entry| !removed.contains(&entry.catalog_entry_id()));
+        catalog
+    }
+
+    fn levels(report: &LspReport) -> Vec<RiskLevel> {
+        report
+            .findings
+            .iter()
+            .map(|finding| finding.level)
+            .collect()
+    }
+
+    #[test]
+    fn stock_catalog_has_no_risk() {
+        let report = LspReport::from_catalog(&catalog(WINDOWS_10, &[]));
+        assert!(report.providers.is_empty());
+        assert!(report.chains.is_empty());
+        assert!(report.intercepted.is_empty());
+        assert_eq!(report.risk, RiskLevel::None);
+        assert!(report.findings.is_empty());
+    }
+
+    #[test]
+    fn finds_layered_provider() {
+        let report = LspReport::from_catalog(&catalog(LSP, &[]));
+        assert_eq!(report.providers.len(), 1);
+        let provider = &report.providers[0];
+        assert_eq!(provider.id, ACME);
+        assert_eq!(provider.name, "Acme Shield LSP");
+        assert_eq!(provider.entries, [1009, 1010, 1011, 1008]);
+
+        let chains: Vec<_> = report
+            .chains
+            .iter()
+            .map(|chain| (chain.entry, chain.layers.as_slice(), chain.base))
+            .collect();
+        assert_eq!(
+            chains,
+            [
+                (1009, &[1008][..], 1001),
+                (1010, &[1008], 1002),
+                (1011, &[1008], 1003),
+            ]
+        );
+        assert!(report.chains.iter().all(|chain| chain.missing.is_empty()));
+
+        let intercepted: Vec<_> = report
+            .intercepted
+            .iter()
+            .map(|protocol| (protocol.entry, protocol.socket_type, &protocol.providers))
+            .collect();
+        assert_eq!(
+            intercepted,
+            [
+                (1001, 1, &vec![ACME]),
+                (1002, 2, &vec![ACME]),
+                (1003, 3, &vec![ACME])
+            ]
+        );
+    }
+
+    #[test]
+    fn intercepted_raw_sockets_are_high_risk() {
+        let report = LspReport::from_catalog(&catalog(LSP, &[]));
+        assert_eq!(report.risk, RiskLevel::High);
+        assert_eq!(
+            levels(&report),
+            [RiskLevel::High, RiskLevel::Medium, RiskLevel::Medium]
+        );
+        assert_eq!(
+            report.findings[0].message,
+            "MSAFD Tcpip [RAW/IP] (1003) is intercepted by Acme Shield LSP, \
+             raw sockets may fail to open or see no traffic"
+        );
+    }
+
+    #[test]
+    fn intercepted_streams_are_medium_risk() {
+        let report = LspReport::from_catalog(&catalog(LSP, &[1011]));
+        assert_eq!(report.risk, RiskLevel::Medium);
+        assert_eq!(report.intercepted.len(), 2);
+        assert_eq!(levels(&report), [RiskLevel::Medium, RiskLevel::Medium]);
+    }
+
+    #[test]
+    fn idle_layers_are_low_risk() {
+        let report = LspReport::from_catalog(&catalog(LSP, &[1009, 1010, 1011]));
+        assert_eq!(report.risk, RiskLevel::Low);
+        assert_eq!(report.providers[0].entries, [1008]);
+        assert!(report.chains.is_empty());
+        assert_eq!(
+            report.findings[0].message,
+            "Acme Shield LSP {6D9B3C2E-41F7-4A0C-9E15-7B2D8F3A5C61} \
+             is installed without intercepting any protocol"
+        );
+    }
+
+    #[test]
+    fn broken_chains_are_high_risk() {
+        let report = LspReport::from_catalog(&catalog(LSP, &[1003, 1009, 1010]));
+        assert_eq!(report.chains[0].missing, [1003]);
+        assert!(report.intercepted.is_empty());
+        assert_eq!(report.risk, RiskLevel::High);
+        assert_eq!(
+            report.findings[0].message,
+            "chain 1011 (Acme Shield over [MSAFD Tcpip [RAW/IP]]) refers to entries [1003], \
+             which aren't in the catalog"
+        );
+    }
+
+    #[test]
+    fn chains_belong_to_their_top_layer() {
+        let report = LspReport::from_catalog(&catalog(CHAIN_GUID, &[]));
+        assert_eq!(report.providers.len(), 1);
+        assert_eq!(report.providers[0].id, ACME);
+        assert_eq!(report.providers[0].entries, [1009, 1010, 1011, 1008]);
+        assert!(report.chains.iter().all(|chain| chain.provider == ACME));
+        assert_eq!(
+            levels(&report),
+            [RiskLevel::High, RiskLevel::Medium, RiskLevel::Medium]
+        );
+        assert_eq!(report, LspReport::from_catalog(&catalog(LSP, &[])));
+    }
+
+    #[test]
+    fn chains_without_their_layer_keep_their_guid() {
+        let report = LspReport::from_catalog(&catalog(CHAIN_GUID, &[1008]));
+        assert_eq!(report.providers.len(), 1);
+        let provider = &report.providers[0];
+        assert_eq!(provider.id, ACME_CHAINS);
+        assert_eq!(provider.name, "Acme Shield over [MSAFD Tcpip [TCP/IP]]");
+        assert_eq!(provider.entries, [1009, 1010, 1011]);
+        assert!(report.chains.iter().all(|chain| chain.missing == [1008]));
+        assert_eq!(report.risk, RiskLevel::High);
+    }
+
+    #[test]
+    fn text_report_golden() {
+        let shown = LspReport::from_catalog(&catalog(LSP, &[])).to_string();
+        golden(
+            "tests/golden/lsp_report.txt",
+            include_str!("../tests/golden/lsp_report.txt"),
+            &shown,
+        );
+    }
+}
@@ -0,0 +1,641 @@
+//! This module holds [`ProtocolInfo`], the owned version of the `WSAPROTOCOL_INFOW` entries
+//! `WSAEnumProtocolsW` reports for every transport protocol installed in the Winsock catalog
+
+use crate::{Result as WsaResult, WsaError};
+use bitflags::bitflags;
+use std::{
+    convert::{TryFrom, TryInto},
+    error::Error,
+    fmt::{Display, Formatter, Result as FmtResult},
+    os::raw::c_int,
+};
+
+/// The most entries a protocol chain can have, `MAX_PROTOCOL_CHAIN`
+pub const MAX_PROTOCOL_CHAIN: usize = 7;
+/// The length of the protocol name, in UTF-16 units, `WSAPROTOCOL_LEN + 1`
+const NAME_LEN: usize = 256;
+
+bitflags! {
+    /// What a protocol supports, `dwServiceFlags1`
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct ServiceFlags: u32 {
+        /// `XP1_CONNECTIONLESS`
+        const CONNECTIONLESS = 0x0000_0001;
+        /// `XP1_GUARANTEED_DELIVERY`
+        const GUARANTEED_DELIVERY = 0x0000_0002;
+        /// `XP1_GUARANTEED_ORDER`
+        const GUARANTEED_ORDER = 0x0000_0004;
+        /// `XP1_MESSAGE_ORIENTED`
+        const MESSAGE_ORIENTED = 0x0000_0008;
+        /// `XP1_PSEUDO_STREAM`
+        const PSEUDO_STREAM = 0x0000_0010;
+        /// `XP1_GRACEFUL_CLOSE`
+        const GRACEFUL_CLOSE = 0x0000_0020;
+        /// `XP1_EXPEDITED_DATA`
+        const EXPEDITED_DATA = 0x0000_0040;
+        /// `XP1_CONNECT_DATA`
+        const CONNECT_DATA = 0x0000_0080;
+        /// `XP1_DISCONNECT_DATA`
+        const DISCONNECT_DATA = 0x0000_0100;
+        /// `XP1_SUPPORT_BROADCAST`
+        const SUPPORT_BROADCAST = 0x0000_0200;
+        /// `XP1_SUPPORT_MULTIPOINT`
+        const SUPPORT_MULTIPOINT = 0x0000_0400;
+        /// `XP1_MULTIPOINT_CONTROL_PLANE`
+        const MULTIPOINT_CONTROL_PLANE = 0x0000_0800;
+        /// `XP1_MULTIPOINT_DATA_PLANE`
+        const MULTIPOINT_DATA_PLANE = 0x0000_1000;
+        /// `XP1_QOS_SUPPORTED`
+        const QOS_SUPPORTED = 0x0000_2000;
+        /// `XP1_INTERRUPT`
+        const INTERRUPT = 0x0000_4000;
+        /// `XP1_UNI_SEND`
+        const UNI_SEND = 0x0000_8000;
+        /// `XP1_UNI_RECV`
+        const UNI_RECV = 0x0001_0000;
+        /// `XP1_IFS_HANDLES`, the sockets are real file handles
+        const IFS_HANDLES = 0x0002_0000;
+        /// `XP1_PARTIAL_MESSAGE`
+        const PARTIAL_MESSAGE = 0x0004_0000;
+        /// `XP1_SAN_SUPPORT_SDP`
+        const SAN_SUPPORT_SDP = 0x0008_0000;
+
+        // Bits Winsock may add later are kept as they are
+        const _ = !0;
+    }
+}
+
+bitflags! {
+    /// How the provider exposes the protocol, `dwProviderFlags`
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct ProviderFlags: u32 {
+        /// `PFL_MULTIPLE_PROTO_ENTRIES`, one of several entries for the same protocol
+        const MULTIPLE_PROTO_ENTRIES = 0x0000_0001;
+        /// `PFL_RECOMMENDED_PROTO_ENTRY`, the preferred of several entries for the same protocol
+        const RECOMMENDED_PROTO_ENTRY = 0x0000_0002;
+        /// `PFL_HIDDEN`, not meant to be shown to users
+        const HIDDEN = 0x0000_0004;
+        /// `PFL_MATCHES_PROTOCOL_ZERO`, picked when a socket asks for protocol 0
+        const MATCHES_PROTOCOL_ZERO = 0x0000_0008;
+        /// `PFL_NETWORKDIRECT_PROVIDER`
+        const NETWORKDIRECT_PROVIDER = 0x0000_0010;
+
+        // Bits Winsock may add later are kept as they are
+        const _ = !0;
+    }
+}
+
+/// A GUID identifying a provider, shown in the registry format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Guid {
+    data1: u32,
+    data2: u16,
+    data3: u16,
+    data4: [u8; 8],
+}
+
+/// Where a protocol entry sits in the catalog, `ProtocolChain`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ProtocolChain {
+    /// A layered protocol, which only exists to be part of chains, `ChainLen` 0
+    Layered,
+    /// A base protocol, implemented by the provider itself, `ChainLen` 1
+    Base,
+    /// A chain of catalog entry ids, from the top layer down to the base protocol
+    Chain(Vec<u32>),
+}
+
+/// A protocol installed in the Winsock catalog, as reported by `WSAEnumProtocolsW`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ProtocolInfo {
+    service_flags: ServiceFlags,
+    provider_flags: ProviderFlags,
+    provider_id: Guid,
+    catalog_entry_id: u32,
+    chain: ProtocolChain,
+    version: i32,
+    address_family: i32,
+    max_sockaddr: i32,
+    min_sockaddr: i32,
+    socket_type: i32,
+    protocol: i32,
+    protocol_max_offset: i32,
+    big_endian: bool,
+    security_scheme: i32,
+    message_size: u32,
+    name: String,
+}
+
+/// Why `WSAPROTOCOL_INFOW` entries couldn't be decoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The buffer doesn't hold a whole number of entries
+    Length {
+        /// How long the buffer was
+        len: usize,
+    },
+    /// An entry's `ChainLen` is negative, or longer than [`MAX_PROTOCOL_CHAIN`]
+    ChainLen {
+        /// The catalog id of the entry
+        entry: u32,
+        /// The `ChainLen` it has
+        len: i32,
+    },
+}
+
+impl Guid {
+    /// A GUID made of its fields, as written in the registry format
+    #[must_use]
+    pub const fn new(data1: u32, data2: u16, data3: u16, data4: [u8; 8]) -> Self {
+        Self {
+            data1,
+            data2,
+            data3,
+            data4,
+        }
+    }
+
+    /// Reads a GUID laid out in memory the way windows does, little endian
+    #[must_use]
+    pub fn from_bytes_le(bytes: [u8; 16]) -> Self {
+        let mut reader = Reader(&bytes);
+        Self {
+            data1: reader.u32(),
+            data2: reader.u16(),
+            data3: reader.u16(),
+            data4: reader.array(),
+        }
+    }
+}
+
+impl Display for Guid {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let (clock_seq, node) = self.data4.split_at(2);
+        write!(
+            f,
+            "{{{:08X}-{:04X}-{:04X}-",
+            self.data1, self.data2, self.data3
+        )?;
+        for byte in clock_seq {
+            write!(f, "{byte:02X}")?;
+        }
+        f.write_str("-")?;
+        for byte in node {
+            write!(f, "{byte:02X}")?;
+        }
+        f.write_str("}")
+    }
+}
+
+impl ProtocolChain {
+    /// The catalog entries making up the chain, empty unless this is a [`Chain`](Self::Chain)
+    #[must_use]
+    pub fn entries(&self) -> &[u32] {
+        match self {
+            Self::Layered | Self::Base => &[],
+            Self::Chain(entries) => entries,
+        }
+    }
+}
+
+impl ProtocolInfo {
+    /// The size of a `WSAPROTOCOL_INFOW`, which is the same on every architecture
+    pub const SIZE: usize = 628;
+
+    /// Decodes a single `WSAPROTOCOL_INFOW` from its bytes, as laid out in memory
+    /// # Errors
+    /// Returns a [`DecodeError`] if `bytes` isn't exactly [`ProtocolInfo::SIZE`] long,
+    /// or if the length of its protocol chain is negative or longer than [`MAX_PROTOCOL_CHAIN`]
+    pub fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+        if bytes.len() != Self::SIZE {
+            return Err(DecodeError::Length { len: bytes.len() });
+        }
+        let mut reader = Reader(bytes);
+        let service_flags = ServiceFlags::from_bits_retain(reader.u32());
+        // dwServiceFlags2 through 4 are reserved
+        reader.skip(12);
+        let provider_flags = ProviderFlags::from_bits_retain(reader.u32());
+        let provider_id = Guid::from_bytes_le(reader.array());
+        let catalog_entry_id = reader.u32();
+        let chain_len = reader.i32();
+        let entries: [u32; MAX_PROTOCOL_CHAIN] = std::array::from_fn(|_| reader.u32());
+        let chain = match chain_len {
+            0 => ProtocolChain::Layered,
+            1 => ProtocolChain::Base,
+            len => match usize::try_from(len) {
+                Ok(chain_len) if chain_len <= MAX_PROTOCOL_CHAIN => {
+                    ProtocolChain::Chain(entries[..chain_len].to_vec())
+                }
+                // Winsock never writes such a chain, so the buffer is corrupt
+                _ => {
+                    return Err(DecodeError::ChainLen {
+                        entry: catalog_entry_id,
+                        len,
+                    })
+                }
+            },
+        };
+        Ok(Self {
+            service_flags,
+            provider_flags,
+            provider_id,
+            catalog_entry_id,
+            chain,
+            version: reader.i32(),
+            address_family: reader.i32(),
+            max_sockaddr: reader.i32(),
+            min_sockaddr: reader.i32(),
+            socket_type: reader.i32(),
+            protocol: reader.i32(),
+            protocol_max_offset: reader.i32(),
+            // BIGENDIAN is 0 and LITTLEENDIAN is 1
+            big_endian: reader.i32() == 0,
+            security_scheme: reader.i32(),
+            message_size: reader.u32(),
+            name: {
+                // dwProviderReserved
+                reader.skip(4);
+                let name: [u16; NAME_LEN] = std::array::from_fn(|_| reader.u16());
+                let len = name.iter().position(|&unit| unit == 0).unwrap_or(NAME_LEN);
+                String::from_utf16_lossy(&name[..len])
+            },
+        })
+    }
+
+    /// Decodes the whole buffer `WSAEnumProtocolsW` filled in, entry after entry
+    /// # Errors
+    /// Returns a [`DecodeError`] if `bytes` isn't a multiple of [`ProtocolInfo::SIZE`] long,
+    /// or if any entry fails to [decode](Self::decode)
+    pub fn decode_catalog(bytes: &[u8]) -> Result<Vec<Self>, DecodeError> {
+        if bytes.len() % Self::SIZE != 0 {
+            return Err(DecodeError::Length { len: bytes.len() });
+        }
+        bytes.chunks_exact(Self::SIZE).map(Self::decode).collect()
+    }
+
+    /// What the protocol supports
+    #[must_use]
+    pub const fn service_flags(&self) -> ServiceFlags {
+        self.service_flags
+    }
+
+    /// How the provider exposes the protocol
+    #[must_use]
+    pub const fn provider_flags(&self) -> ProviderFlags {
+        self.provider_flags
+    }
+
+    /// The GUID of the provider implementing the protocol
+    #[must_use]
+    pub const fn provider_id(&self) -> Guid {
+        self.provider_id
+    }
+
+    /// The id of this entry in the catalog, which chains refer to
+    #[must_use]
+    pub const fn catalog_entry_id(&self) -> u32 {
+        self.catalog_entry_id
+    }
+
+    /// Whether this is a layered, base or chained protocol
+    #[must_use]
+    pub const fn chain(&self) -> &ProtocolChain {
+        &self.chain
+    }
+
+    /// The version of the protocol
+    #[must_use]
+    pub const fn version(&self) -> i32 {
+        self.version
+    }
+
+    /// The address family of the sockets, such as 2 for `AF_INET` or 23 for `AF_INET6`
+    #[must_use]
+    pub const fn address_family(&self) -> i32 {
+        self.address_family
+    }
+
+    /// The largest socket address the protocol takes, in bytes
+    #[must_use]
+    pub const fn max_sockaddr(&self) -> i32 {
+        self.max_sockaddr
+    }
+
+    /// The smallest socket address the protocol takes, in bytes
+    #[must_use]
+    pub const fn min_sockaddr(&self) -> i32 {
+        self.min_sockaddr
+    }
+
+    /// The type of the sockets, such as 1 for `SOCK_STREAM` or 3 for `SOCK_RAW`
+    #[must_use]
+    pub const fn socket_type(&self) -> i32 {
+        self.socket_type
+    }
+
+    /// The protocol of the sockets, such as 6 for `IPPROTO_TCP`
+    #[must_use]
+    pub const fn protocol(&self) -> i32 {
+        self.protocol
+    }
+
+    /// How far above [`protocol`](Self::protocol) sockets may ask for,
+    /// raw sockets take any protocol in that range
+    #[must_use]
+    pub const fn protocol_max_offset(&self) -> i32 {
+        self.protocol_max_offset
+    }
+
+    /// Whether the protocol sends data big endian
+    #[must_use]
+    pub const fn big_endian(&self) -> bool {
+        self.big_endian
+    }
+
+    /// The security scheme of the protocol, 0 for none
+    #[must_use]
+    pub const fn security_scheme(&self) -> i32 {
+        self.security_scheme
+    }
+
+    /// The largest message the protocol supports, 0 for streams
+    #[must_use]
+    pub const fn message_size(&self) -> u32 {
+        self.message_size
+    }
+
+    /// The name of the protocol, such as `MSAFD Tcpip [TCP/IP]`
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Error for DecodeError {}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Length { len } => write!(
+                f,
+                "{len} bytes isn't a whole number of {} byte WSAPROTOCOL_INFOW entries",
+                ProtocolInfo::SIZE
+            ),
+            Self::ChainLen { entry, len } => {
+                write!(f, "entry {entry} has a protocol chain of length {len}")
+            }
+        }
+    }
+}
+
+/// Enumerates the catalog through `enum_protocols`, a `WSAEnumProtocolsW` taking the buffer
+/// and its length in bytes, growing the buffer until the whole catalog fits
+pub(crate) fn enumerate(
+    mut enum_protocols: impl FnMut(*mut u32, *mut u32) -> c_int,
+    last_error: impl Fn() -> Option<WsaError>,
+) -> WsaResult<Vec<ProtocolInfo>> {
+    // Made of u32 so it is aligned for WSAPROTOCOL_INFOW
+    let mut buffer: Vec<u32> = Vec::new();
+    loop {
+        let mut len = u32::try_from(buffer.len() * 4).unwrap_or(u32::MAX);
+        let count = enum_protocols(buffer.as_mut_ptr(), &raw mut len);
+        if let Ok(count) = usize::try_from(count) {
+            let bytes: Vec<u8> = buffer.iter().flat_map(|unit| unit.to_ne_bytes()).collect();
+            // More entries than the buffer has room for can't have been written
+            let bytes = count
+                .checked_mul(ProtocolInfo::SIZE)
+                .and_then(|len| bytes.get(..len))
+                .ok_or(WsaError::InvalidData)?;
+            return ProtocolInfo::decode_catalog(bytes).map_err(|_| WsaError::InvalidData);
+        }
+        match last_error() {
+            // The catalog may have grown since the length was asked for, so this can repeat
+            Some(WsaError::NoBufferSpace) if len as usize > buffer.len() * 4 => {
+                buffer.resize((len as usize).div_ceil(4), 0);
+            }
+            err => return Err(err.unwrap_or(WsaError::Other(count))),
+        }
+    }
+}
+
+/// Reads little endian values off the front of a buffer which is known to be long enough
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn array<const N: usize>(&mut self) -> [u8; N] {
+        let (bytes, rest) = self.0.split_at(N);
+        self.0 = rest;
+        bytes.try_into().expect("split at N")
+    }
+
+    fn skip(&mut self, len: usize) {
+        self.0 = &self.0[len..];
+    }
+
+    fn u16(&mut self) -> u16 {
+        u16::from_le_bytes(self.array())
+    }
+
+    fn u32(&mut self) -> u32 {
+        u32::from_le_bytes(self.array())
+    }
+
+    fn i32(&mut self) -> i32 {
+        i32::from_le_bytes(self.array())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        enumerate, DecodeError, Guid, ProtocolChain, ProtocolInfo, ProviderFlags, ServiceFlags,
+    };
+    use crate::WsaError;
+    use std::{
+        cell::Cell,
+        convert::{TryFrom, TryInto},
+    };
+
+    /// The catalog of a stock Windows 10, as `WSAEnumProtocolsW` fills it in
+    const WINDOWS_10: &[u8] = include_bytes!("../tests/fixtures/catalog/windows10.bin");
+
+    const TCPIP: Guid = Guid::new(
+        0xE70F_1AA0,
+        0xAB8B,
+        0x11CF,
+        [0x8C, 0xA3, 0x00, 0x80, 0x5F, 0x48, 0xA1, 0x92],
+    );
+
+    #[test]
+    fn decodes_catalog() {
+        let catalog = ProtocolInfo::decode_catalog(WINDOWS_10).unwrap();
+        let summary: Vec<_> = catalog
+            .iter()
+            .map(|entry| {
+                (
+                    entry.catalog_entry_id(),
+                    entry.name(),
+                    entry.address_family(),
+                    entry.socket_type(),
+                    entry.protocol(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (1001, "MSAFD Tcpip [TCP/IP]", 2, 1, 6),
+                (1002, "MSAFD Tcpip [UDP/IP]", 2, 2, 17),
+                (1003, "MSAFD Tcpip [RAW/IP]", 2, 3, 0),
+                (1004, "MSAFD Tcpip [TCP/IPv6]", 23, 1, 6),
+                (1005, "MSAFD Tcpip [UDP/IPv6]", 23, 2, 17),
+                (1006, "MSAFD Tcpip [RAW/IPv6]", 23, 3, 0),
+                (1007, "Hyper-V RAW", 34, 1, 1),
+            ]
+        );
+        assert!(catalog
+            .iter()
+            .all(|entry| *entry.chain() == ProtocolChain::Base && entry.version() == 2));
+    }
+
+    #[test]
+    fn decodes_fields() {
+        let catalog = ProtocolInfo::decode_catalog(WINDOWS_10).unwrap();
+        let raw = &catalog[2];
+        assert_eq!(
+            raw.service_flags(),
+            ServiceFlags::CONNECTIONLESS
+                | ServiceFlags::MESSAGE_ORIENTED
+                | ServiceFlags::SUPPORT_BROADCAST
+                | ServiceFlags::SUPPORT_MULTIPOINT
+                | ServiceFlags::IFS_HANDLES
+        );
+        assert_eq!(
+            raw.provider_flags(),
+            ProviderFlags::HIDDEN | ProviderFlags::MATCHES_PROTOCOL_ZERO
+        );
+        assert_eq!(raw.provider_id(), TCPIP);
+        assert_eq!((raw.min_sockaddr(), raw.max_sockaddr()), (16, 16));
+        assert_eq!(raw.protocol_max_offset(), 255);
+        assert_eq!(raw.message_size(), 32768);
+        assert!(raw.big_endian());
+        assert_eq!(raw.security_scheme(), 0);
+
+        let tcp6 = &catalog[3];
+        assert!(tcp6
+            .service_flags()
+            .contains(ServiceFlags::GUARANTEED_DELIVERY | ServiceFlags::GUARANTEED_ORDER));
+        assert_eq!(
+            tcp6.provider_id().to_string(),
+            "{F9EAB0C0-26D4-11D0-BBBF-00AA006C34E4}"
+        );
+        assert_eq!((tcp6.min_sockaddr(), tcp6.max_sockaddr()), (28, 28));
+    }
+
+    #[test]
+    fn decodes_chains_and_unknown_flags() {
+        let mut bytes = WINDOWS_10[..ProtocolInfo::SIZE].to_vec();
+        bytes[..4].copy_from_slice(&0x8000_0001_u32.to_le_bytes());
+        bytes[40..44].copy_from_slice(&3_i32.to_le_bytes());
+        for (at, id) in [(44, 2001_u32), (48, 2002), (52, 1001)] {
+            bytes[at..at + 4].copy_from_slice(&id.to_le_bytes());
+        }
+        let entry = ProtocolInfo::decode(&bytes).unwrap();
+        assert_eq!(entry.service_flags().bits(), 0x8000_0001);
+        assert!(entry.service_flags().contains(ServiceFlags::CONNECTIONLESS));
+        assert_eq!(*entry.chain(), ProtocolChain::Chain(vec![2001, 2002, 1001]));
+        assert_eq!(entry.chain().entries(), [2001, 2002, 1001]);
+
+        bytes[40..44].copy_from_slice(&0_i32.to_le_bytes());
+        let entry = ProtocolInfo::decode(&bytes).unwrap();
+        assert_eq!(*entry.chain(), ProtocolChain::Layered);
+        assert!(entry.chain().entries().is_empty());
+
+        bytes[40..44].copy_from_slice(&7_i32.to_le_bytes());
+        let entry = ProtocolInfo::decode(&bytes).unwrap();
+        assert_eq!(entry.chain().entries().len(), 7);
+        bytes[40..44].copy_from_slice(&8_i32.to_le_bytes());
+        let err = ProtocolInfo::decode(&bytes).unwrap_err();
+        assert_eq!(
+            err,
+            DecodeError::ChainLen {
+                entry: 1001,
+                len: 8
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "entry 1001 has a protocol chain of length 8"
+        );
+        bytes[40..44].copy_from_slice(&(-1_i32).to_le_bytes());
+        let err = ProtocolInfo::decode(&bytes).unwrap_err();
+        assert_eq!(
+            err,
+            DecodeError::ChainLen {
+                entry: 1001,
+                len: -1
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "entry 1001 has a protocol chain of length -1"
+        );
+    }
+
+    #[test]
+    fn rejects_partial_entries() {
+        let err = ProtocolInfo::decode_catalog(&WINDOWS_10[..1000]).unwrap_err();
+        assert_eq!(err, DecodeError::Length { len: 1000 });
+        assert_eq!(
+            err.to_string(),
+            "1000 bytes isn't a whole number of 628 byte WSAPROTOCOL_INFOW entries"
+        );
+        assert!(ProtocolInfo::decode(&WINDOWS_10[..2 * ProtocolInfo::SIZE]).is_err());
+        assert_eq!(ProtocolInfo::decode_catalog(&[]), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn formats_guids() {
+        assert_eq!(TCPIP.to_string(), "{E70F1AA0-AB8B-11CF-8CA3-00805F48A192}");
+        let bytes = WINDOWS_10[20..36].try_into().unwrap();
+        assert_eq!(Guid::from_bytes_le(bytes), TCPIP);
+    }
+
+    #[test]
+    fn enumerate_grows_buffer() {
+        let calls = Cell::new(0);
+        let last_error = Cell::new(None);
+        let catalog = enumerate(
+            |buffer, len| {
+                calls.set(calls.get() + 1);
+                let needed = u32::try_from(WINDOWS_10.len()).unwrap();
+                if unsafe { *len } < needed {
+                    unsafe { *len = needed };
+                    last_error.set(Some(WsaError::NoBufferSpace));
+                    return -1;
+                }
+                unsafe {
+                    std::ptr::copy_nonoverlapping(
+                        WINDOWS_10.as_ptr(),
+                        buffer.cast(),
+                        WINDOWS_10.len(),
+                    );
+                }
+                7
+            },
+            || last_error.get(),
+        )
+        .unwrap();
+        assert_eq!(calls.get(), 2);
+        assert_eq!(catalog, ProtocolInfo::decode_catalog(WINDOWS_10).unwrap());
+
+        let failed = enumerate(|_, _| -1, || Some(WsaError::NotInitialised));
+        assert_eq!(failed, Err(WsaError::NotInitialised));
+    }
+
+    #[test]
+    fn enumerate_rejects_overlong_counts() {
+        let overlong = enumerate(|_, _| 3, || None);
+        assert_eq!(overlong, Err(WsaError::InvalidData));
+    }
+}
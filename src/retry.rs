@@ -0,0 +1,537 @@
+//! This module holds [`RetryPolicy`], how [`WsaInitializer`](crate::WsaInitializer) retries
+//! failed startups, along with the [`Clock`] it waits on and the [`Attempt`]s it reports
+
+use crate::WsaError;
+use std::{
+    collections::hash_map::RandomState,
+    fmt::{Debug, Formatter, Result as FmtResult},
+    hash::{BuildHasher, Hasher},
+    sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How long to wait between attempts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backoff {
+    /// The same delay before every retry
+    Fixed(Duration),
+    /// A delay starting at `initial` and doubling after every retry, up to `max`
+    Exponential {
+        /// The delay before the first retry
+        initial: Duration,
+        /// The longest delay
+        max: Duration,
+    },
+}
+
+impl Backoff {
+    /// The delay before retrying after the `failures`th failed attempt, counting from 1
+    #[must_use]
+    pub fn delay(self, failures: u32) -> Duration {
+        match self {
+            Self::Fixed(delay) => delay,
+            Self::Exponential { initial, max } => {
+                let factor = 1_u32.checked_shl(failures.saturating_sub(1));
+                factor
+                    .and_then(|factor| initial.checked_mul(factor))
+                    .map_or(max, |delay| delay.min(max))
+            }
+        }
+    }
+}
+
+/// Where a [`RetryPolicy`] gets the time from, and how it waits
+pub trait Clock {
+    /// The time passed since some fixed point, which only moves forward
+    fn now(&self) -> Duration;
+
+    /// Waits for `duration`
+    fn sleep(&self, duration: Duration);
+}
+
+/// The system's monotonic clock, sleeping the current thread
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        static START: OnceLock<Instant> = OnceLock::new();
+        START.get_or_init(Instant::now).elapsed()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+/// A clock that only moves when slept on, for testing retries without waiting.\
+/// Clones share the same time
+#[derive(Debug, Clone, Default)]
+pub struct SimulatedClock(Arc<Mutex<SimulatedTime>>);
+
+#[derive(Debug, Default)]
+struct SimulatedTime {
+    now: Duration,
+    sleeps: Vec<Duration>,
+}
+
+impl SimulatedClock {
+    /// A clock starting at zero
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Moves the time forward by `duration` without counting it as a sleep
+    pub fn advance(&self, duration: Duration) {
+        self.time().now += duration;
+    }
+
+    /// Every sleep so far, in order
+    #[must_use]
+    pub fn sleeps(&self) -> Vec<Duration> {
+        self.time().sleeps.clone()
+    }
+
+    fn time(&self) -> MutexGuard<'_, SimulatedTime> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Clock for SimulatedClock {
+    fn now(&self) -> Duration {
+        self.time().now
+    }
+
+    fn sleep(&self, duration: Duration) {
+        let mut time = self.time();
+        time.now += duration;
+        time.sleeps.push(duration);
+    }
+}
+
+/// How many times to try starting WSA up, and how long to wait in between.\
+/// Only errors the policy deems retryable are retried, [transient](WsaError::is_transient) ones by default
+#[derive(Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    backoff: Backoff,
+    jitter: f64,
+    deadline: Option<Duration>,
+    retryable: Arc<dyn Fn(WsaError) -> bool + Send + Sync>,
+    clock: Arc<dyn Clock + Send + Sync>,
+    seed: Option<u64>,
+}
+
+impl Default for RetryPolicy {
+    /// 3 attempts, backing off exponentially from 100ms up to 5s, without jitter or a deadline
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff: Backoff::Exponential {
+                initial: Duration::from_millis(100),
+                max: Duration::from_secs(5),
+            },
+            jitter: 0.0,
+            deadline: None,
+            retryable: Arc::new(WsaError::is_transient),
+            clock: Arc::new(SystemClock),
+            seed: None,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The [default](Self::default) policy
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how many attempts to make in total, including the first one
+    #[must_use]
+    pub fn max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    /// Sets how long to wait between attempts
+    #[must_use]
+    pub const fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Sets the fraction of each delay, between 0 and 1, that may randomly be cut from it,
+    /// so processes starting together don't retry together. NaN turns jitter off
+    #[must_use]
+    pub const fn jitter(mut self, fraction: f64) -> Self {
+        self.jitter = if fraction.is_nan() {
+            0.0
+        } else {
+            fraction.clamp(0.0, 1.0)
+        };
+        self
+    }
+
+    /// Sets how long all attempts together may take, no retry is made that would wait past it
+    #[must_use]
+    pub const fn deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Sets which errors are worth retrying
+    #[must_use]
+    pub fn retry_if(
+        mut self,
+        retryable: impl Fn(WsaError) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.retryable = Arc::new(retryable);
+        self
+    }
+
+    /// Sets the clock to wait on, the [`SystemClock`] by default
+    #[must_use]
+    pub fn clock(mut self, clock: impl Clock + Send + Sync + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Seeds the jitter, so the delays are the same on every run
+    #[must_use]
+    pub const fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Runs `attempt` until it succeeds, fails with an error that isn't retryable,
+    /// or the attempts or the time run out. Every attempt made is pushed to `report`
+    pub(crate) fn run<T>(
+        &self,
+        report: &mut Vec<Attempt>,
+        mut attempt: impl FnMut() -> Result<T, WsaError>,
+    ) -> Result<T, WsaError> {
+        // xorshift never leaves a zero state, so zero is swapped for an arbitrary odd constant
+        const NONZERO: u64 = 0x9E37_79B9_7F4A_7C15;
+        let start = self.clock.now();
+        let mut random = match self
+            .seed
+            .unwrap_or_else(|| RandomState::new().build_hasher().finish())
+        {
+            0 => NONZERO,
+            seed => seed,
+        };
+        let mut number = 0;
+        loop {
+            number += 1;
+            let at = self.clock.now().saturating_sub(start);
+            let result = attempt();
+            let mut made = Attempt {
+                number,
+                at,
+                error: result.as_ref().err().copied(),
+                delay: None,
+            };
+            let err = match result {
+                Ok(value) => {
+                    report.push(made);
+                    return Ok(value);
+                }
+                Err(err) => err,
+            };
+            if number >= self.max_attempts || !(self.retryable)(err) {
+                report.push(made);
+                return Err(err);
+            }
+            let delay = self.jittered(self.backoff.delay(number), &mut random);
+            let elapsed = self.clock.now().saturating_sub(start);
+            let past_deadline = self.deadline.is_some_and(|deadline| {
+                elapsed.checked_add(delay).is_none_or(|end| end > deadline)
+            });
+            if past_deadline {
+                report.push(made);
+                return Err(err);
+            }
+            made.delay = Some(delay);
+            report.push(made);
+            self.clock.sleep(delay);
+        }
+    }
+
+    fn jittered(&self, delay: Duration, random: &mut u64) -> Duration {
+        if self.jitter == 0.0 {
+            return delay;
+        }
+        // xorshift64, good enough to spread retries apart
+        *random ^= *random << 13;
+        *random ^= *random >> 7;
+        *random ^= *random << 17;
+        // The upper 32 bits, as a fraction of the range they cover
+        #[allow(clippy::cast_possible_truncation)]
+        let fraction = f64::from((*random >> 32) as u32) / f64::from(u32::MAX);
+        // Rounding can push the longest delays past what a Duration holds, those are kept as they are
+        let factor = self.jitter.mul_add(-fraction, 1.0);
+        Duration::try_from_secs_f64(delay.as_secs_f64() * factor)
+            .map_or(delay, |cut| cut.min(delay))
+    }
+}
+
+impl Debug for RetryPolicy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("backoff", &self.backoff)
+            .field("jitter", &self.jitter)
+            .field("deadline", &self.deadline)
+            .field("seed", &self.seed)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A startup attempt, see [`WsaInitializer::init_reporting`](crate::WsaInitializer::init_reporting)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Attempt {
+    /// Which attempt this was, counting from 1
+    pub number: u32,
+    /// When the attempt was made, relative to the first one
+    pub at: Duration,
+    /// What the attempt failed with, [`None`] if it succeeded
+    pub error: Option<WsaError>,
+    /// How long was waited before the next attempt, [`None`] if there was none
+    pub delay: Option<Duration>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Attempt, Backoff, Clock, RetryPolicy, SimulatedClock};
+    use crate::WsaError;
+    use std::time::Duration;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    /// Runs `policy` over `results` in order, the last one repeating
+    fn run(policy: &RetryPolicy, results: &[Result<(), WsaError>]) -> Vec<Attempt> {
+        let mut report = Vec::new();
+        let mut results = results.iter().copied();
+        let mut last = Ok(());
+        let _ = policy.run(&mut report, || {
+            last = results.next().unwrap_or(last);
+            last
+        });
+        report
+    }
+
+    #[test]
+    fn backoffs() {
+        assert_eq!(Backoff::Fixed(MS * 7).delay(5), MS * 7);
+        let exponential = Backoff::Exponential {
+            initial: MS * 10,
+            max: MS * 100,
+        };
+        let delays: Vec<_> = (1..=6)
+            .map(|failures| exponential.delay(failures))
+            .collect();
+        assert_eq!(
+            delays,
+            [MS * 10, MS * 20, MS * 40, MS * 80, MS * 100, MS * 100]
+        );
+        assert_eq!(exponential.delay(u32::MAX), MS * 100);
+    }
+
+    #[test]
+    fn retries_transient_errors() {
+        let clock = SimulatedClock::new();
+        let policy = RetryPolicy::new()
+            .max_attempts(5)
+            .backoff(Backoff::Fixed(MS * 50))
+            .clock(clock.clone());
+        let report = run(
+            &policy,
+            &[
+                Err(WsaError::SystemNotReady),
+                Err(WsaError::TasksLimitReached),
+                Ok(()),
+            ],
+        );
+        assert_eq!(
+            report,
+            [
+                Attempt {
+                    number: 1,
+                    at: Duration::ZERO,
+                    error: Some(WsaError::SystemNotReady),
+                    delay: Some(MS * 50),
+                },
+                Attempt {
+                    number: 2,
+                    at: MS * 50,
+                    error: Some(WsaError::TasksLimitReached),
+                    delay: Some(MS * 50),
+                },
+                Attempt {
+                    number: 3,
+                    at: MS * 100,
+                    error: None,
+                    delay: None,
+                },
+            ]
+        );
+        assert_eq!(clock.sleeps(), [MS * 50, MS * 50]);
+    }
+
+    #[test]
+    fn stops_on_permanent_errors() {
+        let clock = SimulatedClock::new();
+        let policy = RetryPolicy::new().clock(clock.clone());
+        let report = run(&policy, &[Err(WsaError::VersionNotSupported)]);
+        assert_eq!(report.len(), 1);
+        assert!(clock.sleeps().is_empty());
+
+        let policy = policy.retry_if(|err| err == WsaError::VersionNotSupported);
+        let report = run(&policy, &[Err(WsaError::VersionNotSupported)]);
+        assert_eq!(report.len(), 3);
+    }
+
+    #[test]
+    fn stops_after_max_attempts() {
+        let clock = SimulatedClock::new();
+        let policy = RetryPolicy::new()
+            .max_attempts(4)
+            .backoff(Backoff::Exponential {
+                initial: MS * 100,
+                max: MS * 250,
+            })
+            .clock(clock.clone());
+        let report = run(&policy, &[Err(WsaError::SystemNotReady)]);
+        assert_eq!(report.len(), 4);
+        assert_eq!(report[3].delay, None);
+        assert_eq!(clock.sleeps(), [MS * 100, MS * 200, MS * 250]);
+    }
+
+    #[test]
+    fn respects_deadline() {
+        let clock = SimulatedClock::new();
+        let policy = RetryPolicy::new()
+            .max_attempts(10)
+            .backoff(Backoff::Fixed(MS * 300))
+            .deadline(MS * 1000)
+            .clock(clock.clone());
+        let report = run(&policy, &[Err(WsaError::SystemNotReady)]);
+        assert_eq!(report.len(), 4);
+        assert_eq!(clock.sleeps(), [MS * 300; 3]);
+        assert_eq!(clock.now(), MS * 900);
+    }
+
+    #[test]
+    fn deadline_counts_time_spent_in_attempts() {
+        let clock = SimulatedClock::new();
+        let policy = RetryPolicy::new()
+            .max_attempts(10)
+            .backoff(Backoff::Fixed(MS * 100))
+            .deadline(MS * 1000)
+            .clock(clock.clone());
+        let mut report = Vec::new();
+        let result: Result<(), _> = policy.run(&mut report, || {
+            clock.advance(MS * 400);
+            Err(WsaError::SystemNotReady)
+        });
+        assert_eq!(result, Err(WsaError::SystemNotReady));
+        let at: Vec<_> = report.iter().map(|attempt| attempt.at).collect();
+        assert_eq!(at, [Duration::ZERO, MS * 500, MS * 1000]);
+        assert_eq!(report[2].delay, None);
+    }
+
+    #[test]
+    fn seeded_jitter_is_deterministic() {
+        let delays = |seed| {
+            let clock = SimulatedClock::new();
+            let policy = RetryPolicy::new()
+                .max_attempts(6)
+                .backoff(Backoff::Fixed(MS * 1000))
+                .jitter(0.5)
+                .seed(seed)
+                .clock(clock.clone());
+            run(&policy, &[Err(WsaError::SystemNotReady)]);
+            clock.sleeps()
+        };
+        let sleeps = delays(42);
+        assert_eq!(sleeps, delays(42));
+        assert_ne!(sleeps, delays(7));
+        for sleep in sleeps {
+            assert_eq!(
+                sleep.clamp(MS * 500, MS * 1000),
+                sleep,
+                "{sleep:?} is outside the jitter"
+            );
+        }
+    }
+
+    #[test]
+    fn zero_seed_still_jitters() {
+        let clock = SimulatedClock::new();
+        let policy = RetryPolicy::new()
+            .max_attempts(4)
+            .backoff(Backoff::Fixed(MS * 1000))
+            .jitter(1.0)
+            .seed(0)
+            .clock(clock.clone());
+        run(&policy, &[Err(WsaError::SystemNotReady)]);
+        assert!(clock.sleeps().iter().any(|&sleep| sleep != MS * 1000));
+    }
+
+    #[test]
+    fn nan_jitter_is_off() {
+        let clock = SimulatedClock::new();
+        let policy = RetryPolicy::new()
+            .backoff(Backoff::Fixed(MS * 10))
+            .jitter(f64::NAN)
+            .clock(clock.clone());
+        run(&policy, &[Err(WsaError::SystemNotReady)]);
+        assert_eq!(clock.sleeps(), [MS * 10; 2]);
+    }
+
+    #[test]
+    fn jitters_huge_delays() {
+        let max = Duration::MAX;
+        let backoff = Backoff::Exponential {
+            initial: max / 4,
+            max,
+        };
+        let policy = RetryPolicy::new().backoff(backoff).jitter(0.5);
+        let mut random = 1;
+        let mut cuts = Vec::new();
+        for failures in 1..=8 {
+            let delay = backoff.delay(failures);
+            // At most half of the delay is cut, give or take what f64 rounds off such long delays
+            let shortest = delay.mul_f64(0.499_999);
+            let cut = policy.jittered(delay, &mut random);
+            assert_eq!(
+                cut.clamp(shortest, delay),
+                cut,
+                "{cut:?} is outside the jitter of {delay:?}"
+            );
+            cuts.push(cut);
+        }
+        assert!(cuts[2..].iter().any(|cut| *cut < max));
+    }
+
+    #[test]
+    fn huge_delay_is_past_deadline() {
+        let clock = SimulatedClock::new();
+        clock.advance(MS);
+        let policy = RetryPolicy::new()
+            .backoff(Backoff::Fixed(Duration::MAX))
+            .deadline(MS * 1000)
+            .clock(clock.clone());
+        let mut report = Vec::new();
+        let result: Result<(), _> = policy.run(&mut report, || {
+            clock.advance(MS);
+            Err(WsaError::SystemNotReady)
+        });
+        assert_eq!(result, Err(WsaError::SystemNotReady));
+        assert_eq!(report.len(), 1);
+        assert!(clock.sleeps().is_empty());
+    }
+}
@@ -0,0 +1,179 @@
+//! This module holds the `serde` implementations, enabled by the `serde` feature.
+//!
+//! The schema is stable, shown here as JSON:
+//! - [`WsaVersion`] is a `"major.minor"` string, such as `"2.2"`
+//! - [`WsaError`] is an object with the numeric `code` and the symbolic `name`,
+//!   `{"code": 10091, "name": "WSASYSNOTREADY"}`. The name is `null` for codes outside the catalogue,
+//!   and may be left out when deserializing, but has to match the code if it isn't
+//! - [`WsaInfo`](crate::WsaInfo) is an object with every field the getters of the same names return,
+//!   `{"version": "2.2", "high_version": "2.2", "description": "WinSock 2.0",
+//!   "system_status": "Running", "max_sockets": 0, "max_udp_datagram": 0}`
+//! - [`Guid`] is a string in the registry format, `"{E70F1AA0-AB8B-11CF-8CA3-00805F48A192}"`
+//! - [`LspReport`](crate::LspReport) is an object with every field of the same name,
+//!   risk levels being `"none"`, `"low"`, `"medium"` or `"high"`. It can only be serialized
+
+use crate::{protocol::Guid, WsaError, WsaVersion};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+impl Serialize for WsaVersion {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for WsaVersion {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let version = String::deserialize(deserializer)?;
+        version.parse().map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for Guid {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorRef {
+    code: i32,
+    name: Option<&'static str>,
+}
+
+#[derive(Deserialize)]
+struct ErrorOwned {
+    code: i32,
+    #[serde(default)]
+    name: Option<String>,
+}
+
+impl Serialize for WsaError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ErrorRef {
+            code: self.code(),
+            name: self.name(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for WsaError {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let ErrorOwned { code, name } = ErrorOwned::deserialize(deserializer)?;
+        let err = Self::from(code);
+        match name {
+            Some(name) if Some(name.as_str()) != err.name() => Err(D::Error::custom(format!(
+                "the name {name} doesn't match the code {code}"
+            ))),
+            _ => Ok(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{LspReport, ProtocolInfo, WsaError, WsaInfo, WsaVersion};
+    use serde_json::{from_str, json, to_value, Value};
+
+    #[test]
+    fn versions() {
+        assert_eq!(to_value(WsaVersion::new(1, 1)).unwrap(), json!("1.1"));
+        assert_eq!(
+            from_str::<WsaVersion>("\"2.0\"").unwrap(),
+            WsaVersion::new(2, 0)
+        );
+        assert!(from_str::<WsaVersion>("\"2\"").is_err());
+        assert!(from_str::<WsaVersion>("514").is_err());
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            to_value(WsaError::SystemNotReady).unwrap(),
+            json!({"code": 10091, "name": "WSASYSNOTREADY"})
+        );
+        assert_eq!(
+            to_value(WsaError::Other(42)).unwrap(),
+            json!({"code": 42, "name": null})
+        );
+        assert_eq!(
+            from_str::<WsaError>(r#"{"code": 10092}"#).unwrap(),
+            WsaError::VersionNotSupported
+        );
+        assert!(from_str::<WsaError>(r#"{"code": 10092, "name": "WSAEINTR"}"#).is_err());
+        assert!(from_str::<WsaError>(r#"{"code": 42, "name": "WSAEINTR"}"#).is_err());
+    }
+
+    #[test]
+    fn errors_round_trip() {
+        for &err in WsaError::ALL.iter().chain(&[WsaError::Other(-7)]) {
+            let value = to_value(err).unwrap();
+            assert_eq!(serde_json::from_value::<WsaError>(value).unwrap(), err);
+        }
+    }
+
+    #[test]
+    fn info() {
+        let info = WsaInfo::new(
+            WsaVersion::new(2, 0),
+            WsaVersion::V2_2,
+            "WinSock 2.0",
+            "Running",
+        );
+        let value = to_value(&info).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "version": "2.0",
+                "high_version": "2.2",
+                "description": "WinSock 2.0",
+                "system_status": "Running",
+                "max_sockets": 0,
+                "max_udp_datagram": 0,
+            })
+        );
+        assert_eq!(serde_json::from_value::<WsaInfo>(value).unwrap(), info);
+        let missing: Value = json!({"version": "2.2"});
+        assert!(serde_json::from_value::<WsaInfo>(missing).is_err());
+    }
+
+    #[test]
+    fn lsp_report() {
+        let catalog =
+            ProtocolInfo::decode_catalog(include_bytes!("../tests/fixtures/catalog/lsp.bin"))
+                .unwrap();
+        let value = to_value(LspReport::from_catalog(&catalog[2..])).unwrap();
+        assert_eq!(value["risk"], json!("high"));
+        assert_eq!(
+            value["providers"],
+            json!([{
+                "id": "{6D9B3C2E-41F7-4A0C-9E15-7B2D8F3A5C61}",
+                "name": "Acme Shield LSP",
+                "entries": [1011, 1008],
+            }])
+        );
+        assert_eq!(
+            value["chains"],
+            json!([{
+                "entry": 1011,
+                "name": "Acme Shield over [MSAFD Tcpip [RAW/IP]]",
+                "provider": "{6D9B3C2E-41F7-4A0C-9E15-7B2D8F3A5C61}",
+                "layers": [1008],
+                "base": 1003,
+                "missing": [],
+            }])
+        );
+        assert_eq!(
+            value["intercepted"],
+            json!([{
+                "entry": 1003,
+                "name": "MSAFD Tcpip [RAW/IP]",
+                "address_family": 2,
+                "socket_type": 3,
+                "protocol": 0,
+                "providers": ["{6D9B3C2E-41F7-4A0C-9E15-7B2D8F3A5C61}"],
+            }])
+        );
+        assert_eq!(value["findings"][0]["level"], json!("high"));
+    }
+}
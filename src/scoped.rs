@@ -0,0 +1,34 @@
+//! This module holds [`Scoped`], what running a closure while WSA is initialized returns
+
+use crate::{Backend, Result, Wsa};
+
+/// The value returned by a closure run while WSA was initialized,
+/// see [`WsaInitializer::with_wsa`](crate::WsaInitializer::with_wsa)
+#[must_use = "The cleanup may have failed, see `.into_result`"]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scoped<R> {
+    /// The value the closure returned
+    pub value: R,
+    /// How cleaning up WSA after the closure returned went
+    pub cleanup: Result<()>,
+}
+
+impl<R> Scoped<R> {
+    /// The value the closure returned, or the [`WsaError`](crate::WsaError) cleaning up failed with
+    /// # Errors
+    /// Returns the [`WsaError`](crate::WsaError) `WSACleanup` failed with, dropping the value
+    pub fn into_result(self) -> Result<R> {
+        self.cleanup.map(|()| self.value)
+    }
+}
+
+/// Cleans up WSA if the closure unwinds, emptied once it returns
+pub struct UnwindGuard<B: Backend>(pub Option<Wsa<B>>);
+
+impl<B: Backend> Drop for UnwindGuard<B> {
+    fn drop(&mut self) {
+        if let Some(wsa) = self.0.take() {
+            wsa.clean();
+        }
+    }
+}
@@ -0,0 +1,303 @@
+//! This module holds a process wide, reference counted WSA initialization,
+//! so independent parts of a program can't clean WSA up from under each other
+
+use crate::{trace, Backend, CleanupPolicy, Result, WsaInfo, WsaInitializer};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// The handles currently alive, and the backend, cleanup policy and startup info the first one initialized WSA with
+struct State {
+    holders: usize,
+    backend: Option<Box<dyn Backend + Send>>,
+    cleanup: CleanupPolicy,
+    info: Option<WsaInfo>,
+}
+
+static STATE: Mutex<State> = Mutex::new(State {
+    holders: 0,
+    backend: None,
+    cleanup: CleanupPolicy::Ignore,
+    info: None,
+});
+
+/// Held by whoever is starting WSA up for the first handle, so startups, along with their retries,
+/// don't hold up handles being counted, cloned and dropped
+static STARTUP: Mutex<()> = Mutex::new(());
+
+fn state() -> MutexGuard<'static, State> {
+    STATE.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Adds a holder if WSA is already started up, returning whether it was
+fn join() -> bool {
+    let mut state = state();
+    let started = state.holders > 0;
+    if started {
+        state.holders += 1;
+    }
+    started
+}
+
+/// A cloneable handle that keeps WSA initialized for the whole process.
+///
+/// The first handle calls `WSAStartup`, dropping the last one calls `WSACleanup`
+/// and handles its failure according to the first handle's [`CleanupPolicy`]
+#[derive(Debug)]
+pub struct SharedWsa(());
+
+impl SharedWsa {
+    /// Acquires a handle, initializing WSA with version 2.2 if no other handle is alive
+    /// # Errors
+    /// Returns a [`WsaError`](crate::WsaError) if this is the first handle and `WSAStartup` fails
+    pub fn acquire() -> Result<Self> {
+        Self::acquire_with(WsaInitializer::default())
+    }
+
+    /// Acquires a handle, starting WSA up with `initializer` if no other handle is alive.\
+    /// Only other first handles wait for the startup and its retries, everything else goes on meanwhile
+    pub(crate) fn acquire_with<B>(initializer: WsaInitializer<B>) -> Result<Self>
+    where
+        B: Backend + Clone + Send + 'static,
+    {
+        if join() {
+            return Ok(Self(()));
+        }
+        let _startup = STARTUP.lock().unwrap_or_else(PoisonError::into_inner);
+        // Another first handle may have started WSA up while this one waited
+        if join() {
+            return Ok(Self(()));
+        }
+        // No handle is alive, so nothing else touches the state until this one is added
+        let wsa = initializer.init()?;
+        let mut state = state();
+        // The cleanup is owned by the last handle from now on
+        state.backend = Some(Box::new(wsa.backend.clone()));
+        state.cleanup = wsa.cleanup.clone();
+        state.info = Some(wsa.info.clone());
+        wsa.forget();
+        state.holders += 1;
+        drop(state);
+        Ok(Self(()))
+    }
+
+    /// The amount of handles currently keeping WSA initialized
+    #[must_use]
+    pub fn count() -> usize {
+        state().holders
+    }
+
+    /// The [`WsaInfo`] reported by the startup this handle shares, which stays around as long as the handle does
+    #[allow(clippy::unused_self)]
+    pub(crate) fn info(&self) -> WsaInfo {
+        state()
+            .info
+            .clone()
+            .expect("WSA is started up while a handle is alive")
+    }
+}
+
+impl Clone for SharedWsa {
+    fn clone(&self) -> Self {
+        state().holders += 1;
+        Self(())
+    }
+}
+
+impl Drop for SharedWsa {
+    fn drop(&mut self) {
+        let mut state = state();
+        state.holders -= 1;
+        if state.holders > 0 {
+            return;
+        }
+        // The lock is held so no one can start WSA up while it is cleaned
+        let result = state
+            .backend
+            .take()
+            .map_or(Ok(()), |backend| trace::cleanup(backend.cleanup()));
+        let cleanup = std::mem::take(&mut state.cleanup);
+        state.info = None;
+        // The failure is handled without the lock, as the policy may well acquire a handle of its own
+        drop(state);
+        if let Err(err) = result {
+            cleanup.handle(err);
+        }
+    }
+}
+
+#[cfg(all(test, not(windows)))]
+mod tests {
+    use super::SharedWsa;
+    use crate::{
+        retry::{Backoff, Clock, RetryPolicy},
+        sys, CleanupPolicy, Simulated, WsaError, WsaInitializer,
+    };
+    use std::{
+        sync::{
+            mpsc::{self, Receiver, Sender},
+            Arc, Mutex,
+        },
+        thread,
+        time::Duration,
+    };
+
+    /// A clock whose sleeps last until the test lets them go
+    struct Gate {
+        asleep: Sender<()>,
+        wake: Mutex<Receiver<()>>,
+    }
+
+    impl Clock for Gate {
+        fn now(&self) -> Duration {
+            Duration::ZERO
+        }
+
+        fn sleep(&self, _: Duration) {
+            self.asleep.send(()).unwrap();
+            self.wake.lock().unwrap().recv().unwrap();
+        }
+    }
+
+    #[test]
+    fn cleans_up_through_first_backend() {
+        let _serial = sys::serial();
+        let simulated = Simulated::new();
+        let first = WsaInitializer::with_backend(simulated.clone())
+            .shared()
+            .unwrap();
+        let second = SharedWsa::acquire().unwrap();
+        assert_eq!((simulated.startups(), sys::startups()), (1, 0));
+
+        drop(first);
+        drop(second);
+        assert_eq!((SharedWsa::count(), simulated.startups()), (0, 0));
+    }
+
+    #[test]
+    fn last_handle_follows_cleanup_policy() {
+        let _serial = sys::serial();
+        let simulated = Simulated::new();
+        let failures = Arc::new(Mutex::new(Vec::new()));
+        let mut initializer = WsaInitializer::with_backend(simulated.clone());
+        initializer.cleanup_policy(CleanupPolicy::callback({
+            let failures = Arc::clone(&failures);
+            move |err| failures.lock().unwrap().push((err, SharedWsa::count()))
+        }));
+        let first = initializer.shared().unwrap();
+        let second = first.clone();
+        simulated.fail_cleanup(WsaError::NetworkDown);
+
+        drop(first);
+        assert!(failures.lock().unwrap().is_empty());
+        drop(second);
+        assert_eq!(*failures.lock().unwrap(), [(WsaError::NetworkDown, 0)]);
+
+        // The policy doesn't outlive the handles it was set for
+        simulated.fail_cleanup(WsaError::NetworkDown);
+        drop(WsaInitializer::with_backend(simulated).shared().unwrap());
+        assert_eq!(failures.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn first_starts_last_cleans() {
+        let _serial = sys::serial();
+        let first = SharedWsa::acquire().unwrap();
+        assert_eq!((SharedWsa::count(), sys::startups()), (1, 1));
+
+        let second = first.clone();
+        let third = SharedWsa::acquire().unwrap();
+        assert_eq!((SharedWsa::count(), sys::startups()), (3, 1));
+
+        drop(first);
+        drop(third);
+        assert_eq!((SharedWsa::count(), sys::startups()), (1, 1));
+
+        drop(second);
+        assert_eq!((SharedWsa::count(), sys::startups()), (0, 0));
+    }
+
+    #[test]
+    fn restarts_after_last_drop() {
+        let _serial = sys::serial();
+        for _ in 0..3 {
+            let wsa = SharedWsa::acquire().unwrap();
+            assert_eq!(sys::startups(), 1);
+            drop(wsa);
+            assert_eq!(sys::startups(), 0);
+        }
+    }
+
+    #[test]
+    fn concurrent_handles_balance() {
+        let _serial = sys::serial();
+        let threads: Vec<_> = (0..8)
+            .map(|_| {
+                thread::spawn(|| {
+                    for _ in 0..1000 {
+                        let wsa = SharedWsa::acquire().unwrap();
+                        let clone = wsa.clone();
+                        assert!(SharedWsa::count() >= 2);
+                        assert_eq!(sys::startups(), 1);
+                        drop(wsa);
+                        drop(clone);
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!((SharedWsa::count(), sys::startups()), (0, 0));
+    }
+
+    #[test]
+    fn concurrent_holder_keeps_wsa_alive() {
+        let _serial = sys::serial();
+        let anchor = SharedWsa::acquire().unwrap();
+        let threads: Vec<_> = (0..8)
+            .map(|_| {
+                let wsa = anchor.clone();
+                thread::spawn(move || {
+                    for _ in 0..1000 {
+                        drop(wsa.clone());
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!((SharedWsa::count(), sys::startups()), (1, 1));
+        drop(anchor);
+        assert_eq!((SharedWsa::count(), sys::startups()), (0, 0));
+    }
+
+    #[test]
+    fn retries_without_holding_handles_up() {
+        let _serial = sys::serial();
+        let (asleep, sleeping) = mpsc::channel();
+        let (wake, woken) = mpsc::channel();
+        let simulated = Simulated::new();
+        simulated.fail_startup(WsaError::SystemNotReady);
+        let mut initializer = WsaInitializer::with_backend(simulated.clone());
+        initializer.retry_policy(
+            RetryPolicy::new()
+                .backoff(Backoff::Fixed(Duration::from_secs(1)))
+                .clock(Gate {
+                    asleep,
+                    wake: Mutex::new(woken),
+                }),
+        );
+        let first = thread::spawn(move || initializer.shared());
+        sleeping.recv().unwrap();
+
+        let (counted, count) = mpsc::channel();
+        thread::spawn(move || counted.send(SharedWsa::count()).unwrap());
+        assert_eq!(count.recv_timeout(Duration::from_secs(10)), Ok(0));
+
+        wake.send(()).unwrap();
+        let first = first.join().unwrap().unwrap();
+        assert_eq!((SharedWsa::count(), simulated.startups()), (1, 1));
+        drop(first);
+        assert_eq!(simulated.startups(), 0);
+    }
+}
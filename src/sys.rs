@@ -0,0 +1,173 @@
+//! The raw Winsock calls this crate is built on.
+//! On windows these are the real `ws2_32` functions, anywhere else they are no-ops reporting a synthetic Winsock 2.2
+//! and keeping count of startups so the logic around them can be tested.
+//! A startup for any version from 1.0 up succeeds, a version below it or an unbalanced cleanup fails the way Winsock's does.
+//!
+//! On windows the bindings come from `windows-sys` when its feature is enabled, otherwise from `winapi`.
+//! Without either, `ws2_32` isn't linked at all and is loaded at runtime through the `dynamic` feature.
+
+#[cfg(all(
+    windows,
+    not(any(feature = "winapi", feature = "windows-sys", feature = "dynamic"))
+))]
+compile_error!(
+    "either the `winapi` or the `windows-sys` feature is needed to bind to ws2_32, \
+     or the `dynamic` feature to load it at runtime"
+);
+
+#[cfg(all(windows, feature = "windows-sys"))]
+pub use windows_sys::Win32::Networking::WinSock::{
+    WSACleanup, WSAEnumProtocolsW, WSAGetLastError, WSASetLastError, WSAStartup, INVALID_SOCKET,
+    SOCKET_ERROR, WSADATA,
+};
+
+#[cfg(all(windows, feature = "winapi", not(feature = "windows-sys")))]
+pub use winapi::um::winsock2::{
+    WSACleanup, WSAEnumProtocolsW, WSAGetLastError, WSASetLastError, WSAStartup, INVALID_SOCKET,
+    SOCKET_ERROR, WSADATA,
+};
+
+#[cfg(all(
+    windows,
+    not(any(feature = "winapi", feature = "windows-sys")),
+    feature = "dynamic"
+))]
+pub use crate::dynamic::process::{
+    WSACleanup, WSAEnumProtocolsW, WSAGetLastError, WSASetLastError, WSAStartup, INVALID_SOCKET,
+    SOCKET_ERROR, WSADATA,
+};
+
+#[cfg(not(windows))]
+pub use noop::{
+    WSACleanup, WSAEnumProtocolsW, WSAGetLastError, WSASetLastError, WSAStartup, INVALID_SOCKET,
+    SOCKET_ERROR, WSADATA,
+};
+
+#[cfg(not(windows))]
+#[allow(non_snake_case, clippy::upper_case_acronyms)]
+mod noop {
+    use std::{
+        cell::Cell,
+        os::raw::{c_char, c_int, c_ushort},
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    pub const WSADESCRIPTION_LEN: usize = 256;
+    pub const WSASYS_STATUS_LEN: usize = 128;
+
+    /// Mirrors the 64 bit layout of the C `WSADATA`
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct WSADATA {
+        pub wVersion: u16,
+        pub wHighVersion: u16,
+        pub iMaxSockets: c_ushort,
+        pub iMaxUdpDg: c_ushort,
+        pub lpVendorInfo: *mut c_char,
+        pub szDescription: [c_char; WSADESCRIPTION_LEN + 1],
+        pub szSystemStatus: [c_char; WSASYS_STATUS_LEN + 1],
+    }
+
+    pub const INVALID_SOCKET: usize = !0;
+    pub const SOCKET_ERROR: c_int = -1;
+
+    const WSAVERNOTSUPPORTED: c_int = 10092;
+    const WSANOTINITIALISED: c_int = 10093;
+
+    /// Any version from 1.0 up to 2.2 is negotiated as is, higher ones are negotiated down to 2.2
+    const HIGH_VERSION: [u8; 2] = [2, 2];
+
+    static STARTUPS: AtomicUsize = AtomicUsize::new(0);
+
+    thread_local! {
+        static LAST_ERROR: Cell<c_int> = const { Cell::new(0) };
+    }
+
+    /// Fails with `WSAVERNOTSUPPORTED` for versions below 1.0, like Winsock does
+    pub unsafe fn WSAStartup(version: u16, data: *mut WSADATA) -> c_int {
+        let [major, minor] = version.to_le_bytes();
+        if major == 0 {
+            return WSAVERNOTSUPPORTED;
+        }
+        if let Some(data) = data.as_mut() {
+            data.wVersion = u16::from_le_bytes([major, minor].min(HIGH_VERSION));
+            data.wHighVersion = u16::from_le_bytes(HIGH_VERSION);
+            fill(&mut data.szDescription, b"WinSock 2.0 (no-op)");
+            fill(&mut data.szSystemStatus, b"Running");
+        }
+        STARTUPS.fetch_add(1, Ordering::SeqCst);
+        0
+    }
+
+    #[allow(clippy::cast_possible_wrap)]
+    fn fill(chars: &mut [c_char], text: &[u8]) {
+        for (char, &byte) in chars.iter_mut().zip(text.iter().chain(&[0])) {
+            *char = byte as c_char;
+        }
+    }
+
+    /// Fails with `WSANOTINITIALISED` without a startup left to clean up, like Winsock does
+    pub unsafe fn WSACleanup() -> c_int {
+        if STARTUPS
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+            .is_ok()
+        {
+            0
+        } else {
+            WSASetLastError(WSANOTINITIALISED);
+            SOCKET_ERROR
+        }
+    }
+
+    /// Mirrors the size and alignment of the C `WSAPROTOCOL_INFOW`
+    #[repr(C)]
+    pub struct WSAPROTOCOL_INFOW([u32; 157]);
+
+    /// The catalog is always empty
+    #[allow(clippy::missing_const_for_fn)]
+    pub unsafe fn WSAEnumProtocolsW(
+        _protocols: *const c_int,
+        _buffer: *mut WSAPROTOCOL_INFOW,
+        len: *mut u32,
+    ) -> c_int {
+        if let Some(len) = len.as_mut() {
+            *len = 0;
+        }
+        0
+    }
+
+    pub unsafe fn WSAGetLastError() -> c_int {
+        LAST_ERROR.with(Cell::get)
+    }
+
+    pub unsafe fn WSASetLastError(error: c_int) {
+        LAST_ERROR.with(|last| last.set(error));
+    }
+
+    /// How many times the no-op was started up and not yet cleaned up
+    #[cfg(test)]
+    pub fn startups() -> usize {
+        STARTUPS.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(all(test, not(windows)))]
+pub use noop::startups;
+
+/// Registers `callback` to run when the process exits, through the C runtime's `atexit`
+pub fn at_exit(callback: extern "C" fn()) {
+    extern "C" {
+        fn atexit(callback: extern "C" fn()) -> std::os::raw::c_int;
+    }
+
+    // atexit can only fail when out of memory, there is nothing to do about that here
+    let _ = unsafe { atexit(callback) };
+}
+
+/// Serializes the tests that rely on the process wide Winsock state
+#[cfg(test)]
+pub fn serial() -> std::sync::MutexGuard<'static, ()> {
+    static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    LOCK.lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
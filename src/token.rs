@@ -0,0 +1,36 @@
+//! This module holds [`WsaToken`], a proof that WSA is initialized for as long as it lives
+
+use std::marker::PhantomData;
+
+/// A zero-sized proof that WSA stays initialized for `'a`, borrowed from a [`WsaRaii`](crate::WsaRaii)
+/// or a [`Wsa`](crate::Wsa).
+///
+/// Take one as a parameter of anything that needs Winsock, and store it alongside the value,
+/// such as a socket, so the compiler rejects that value outliving the cleanup:
+/// ```
+/// use wsa_startup::{WsaInitializer, WsaToken};
+///
+/// struct Socket<'wsa> {
+///     _wsa: WsaToken<'wsa>,
+/// }
+///
+/// impl<'wsa> Socket<'wsa> {
+///     fn new(wsa: WsaToken<'wsa>) -> Self {
+///         Self { _wsa: wsa }
+///     }
+/// }
+///
+/// let raii = WsaInitializer::default().init()?.raii();
+/// let socket = Socket::new(raii.token());
+/// drop(socket);
+/// drop(raii);
+/// # Ok::<_, wsa_startup::WsaError>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WsaToken<'a>(PhantomData<&'a ()>);
+
+impl WsaToken<'_> {
+    pub(crate) const fn new() -> Self {
+        Self(PhantomData)
+    }
+}
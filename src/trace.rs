@@ -0,0 +1,363 @@
+//! This module reports startups and cleanups through `tracing` and `log`,
+//! when the features of the same names are enabled
+
+// Without either feature every report is a no-op
+#![cfg_attr(
+    not(any(feature = "tracing", feature = "log")),
+    allow(unused_variables, clippy::missing_const_for_fn)
+)]
+
+use crate::{tracker::Problem, Result, WsaError, WsaInfo, WsaVersion};
+
+/// A startup in progress, inside the `wsa_startup` span when `tracing` is enabled
+pub struct Startup {
+    #[cfg(feature = "tracing")]
+    span: tracing::span::EnteredSpan,
+}
+
+impl Startup {
+    /// Starts reporting a startup asking for `requested`
+    pub fn enter(requested: WsaVersion) -> Self {
+        #[cfg(feature = "log")]
+        log::debug!("starting up WSA {requested}");
+        Self {
+            #[cfg(feature = "tracing")]
+            span: tracing::info_span!(
+                "wsa_startup",
+                requested = %requested,
+                negotiated = tracing::field::Empty,
+                high = tracing::field::Empty,
+            )
+            .entered(),
+        }
+    }
+
+    /// `WSAStartup` asking for `version` failed with `err`
+    #[allow(clippy::unused_self)]
+    pub fn failed(&self, version: WsaVersion, err: WsaError) {
+        #[cfg(feature = "tracing")]
+        tracing::warn!(version = %version, code = err.code(), error = ?err, "WSAStartup failed");
+        #[cfg(feature = "log")]
+        log::warn!(
+            "WSAStartup asking for {version} failed with {err:?} ({})",
+            err.code()
+        );
+    }
+
+    /// `WSAStartup` negotiated a version that isn't one of the fallbacks, so it is cleaned up
+    #[allow(clippy::unused_self)]
+    pub fn rejected(&self, version: WsaVersion, negotiated: WsaVersion) {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(version = %version, negotiated = %negotiated, "rejected negotiated version");
+        #[cfg(feature = "log")]
+        log::debug!(
+            "WSAStartup asking for {version} negotiated {negotiated}, which isn't acceptable"
+        );
+    }
+
+    /// `WSAStartup` succeeded and reported `info`
+    #[allow(clippy::unused_self)]
+    pub fn succeeded(&self, info: &WsaInfo) {
+        #[cfg(feature = "tracing")]
+        {
+            self.span
+                .record("negotiated", tracing::field::display(info.version()));
+            self.span
+                .record("high", tracing::field::display(info.high_version()));
+            tracing::debug!(description = info.description(), "WSA started up");
+        }
+        #[cfg(feature = "log")]
+        log::debug!(
+            "WSA {} started up, {} supports up to {}",
+            info.version(),
+            info.description(),
+            info.high_version()
+        );
+    }
+}
+
+/// Reports the result of `WSACleanup`, passing it through
+pub fn cleanup(result: Result<()>) -> Result<()> {
+    match result {
+        Ok(()) => {
+            #[cfg(feature = "tracing")]
+            tracing::debug!("WSACleanup succeeded");
+            #[cfg(feature = "log")]
+            log::debug!("WSACleanup succeeded");
+        }
+        Err(err) => {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(code = err.code(), error = ?err, "WSACleanup failed");
+            #[cfg(feature = "log")]
+            log::warn!("WSACleanup failed with {err:?} ({})", err.code());
+        }
+    }
+    result
+}
+
+/// Reports a cleanup failure for [`CleanupPolicy::Log`](crate::CleanupPolicy::Log),
+/// through `tracing` and `log` when either is enabled and to stderr otherwise
+pub fn cleanup_failed(err: WsaError) {
+    #[cfg(feature = "tracing")]
+    tracing::error!(code = err.code(), error = ?err, "failed to clean up WSA");
+    #[cfg(feature = "log")]
+    log::error!("failed to clean up WSA: {err}");
+    #[cfg(not(any(feature = "tracing", feature = "log")))]
+    eprintln!("failed to clean up WSA: {err}");
+}
+
+/// Reports a guard that leaked or was cleaned up too often, found in debug builds.\
+/// Only where the guard was created is reported, the backtrace is left to [`tracker::report`](crate::tracker::report)
+pub fn problem(problem: &Problem) {
+    let (what, site) = match problem {
+        Problem::Leaked(site) => ("dropped without cleaning up", site),
+        Problem::Unbalanced(site) => ("cleaned up more than initialized", site),
+    };
+    #[cfg(feature = "tracing")]
+    tracing::warn!(kind = ?site.kind(), location = %site.location(), "WSA guard {what}");
+    #[cfg(feature = "log")]
+    log::warn!(
+        "WSA guard {what}: {:?} created at {}",
+        site.kind(),
+        site.location()
+    );
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tracing_tests {
+    use crate::{Simulated, WsaError, WsaInitializer};
+    use std::{
+        fmt::{Debug, Write},
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc, Mutex,
+        },
+    };
+    use tracing::{
+        field::{Field, Visit},
+        span::{Attributes, Id, Record},
+        Event, Metadata, Subscriber,
+    };
+
+    /// A subscriber writing down every span, recorded field and event as a line
+    #[derive(Default)]
+    struct Capture {
+        ids: AtomicU64,
+        lines: Arc<Mutex<Vec<String>>>,
+    }
+
+    struct Line(String);
+
+    impl Visit for Line {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            if field.name() == "message" {
+                let _ = write!(self.0, " {value:?}");
+            } else {
+                let _ = write!(self.0, " {}={value:?}", field.name());
+            }
+        }
+    }
+
+    impl Capture {
+        fn push(&self, line: Line) {
+            self.lines.lock().unwrap().push(line.0);
+        }
+    }
+
+    impl Subscriber for Capture {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut line = Line(format!("span {}", span.metadata().name()));
+            span.record(&mut line);
+            self.push(line);
+            Id::from_u64(self.ids.fetch_add(1, Ordering::Relaxed) + 1)
+        }
+
+        fn record(&self, _: &Id, values: &Record<'_>) {
+            let mut line = Line("record".to_owned());
+            values.record(&mut line);
+            self.push(line);
+        }
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut line = Line(event.metadata().level().to_string());
+            event.record(&mut line);
+            self.push(line);
+        }
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    fn capture(f: impl FnOnce()) -> Vec<String> {
+        let capture = Capture::default();
+        let lines = Arc::clone(&capture.lines);
+        tracing::subscriber::with_default(capture, f);
+        let lines = lines.lock().unwrap().clone();
+        lines
+    }
+
+    #[test]
+    fn traces_startup_and_cleanup() {
+        let lines = capture(|| {
+            let simulated = Simulated::new();
+            simulated.fail_startup(WsaError::SystemNotReady);
+            let init = || WsaInitializer::with_backend(simulated.clone()).init();
+            assert!(init().is_err());
+            let wsa = init().unwrap();
+            simulated.fail_cleanup(WsaError::NetworkDown);
+            assert!(wsa.try_clean().is_err());
+            init().unwrap().clean();
+        });
+        assert_eq!(
+            lines,
+            [
+                "span wsa_startup requested=2.2",
+                "WARN WSAStartup failed version=2.2 code=10091 error=SystemNotReady",
+                "span wsa_startup requested=2.2",
+                "record negotiated=2.2",
+                "record high=2.2",
+                "DEBUG WSA started up description=\"Simulated WinSock\"",
+                "WARN WSACleanup failed code=10050 error=NetworkDown",
+                "span wsa_startup requested=2.2",
+                "record negotiated=2.2",
+                "record high=2.2",
+                "DEBUG WSA started up description=\"Simulated WinSock\"",
+                "DEBUG WSACleanup succeeded",
+            ]
+        );
+    }
+
+    #[test]
+    fn traces_rejected_versions() {
+        let lines = capture(|| {
+            let mut initializer = WsaInitializer::with_backend(Simulated::new());
+            initializer.version((3, 0)).fallbacks([(1, 1)]);
+            initializer.init().unwrap().clean();
+        });
+        assert_eq!(
+            &lines[..3],
+            [
+                "span wsa_startup requested=3.0",
+                "DEBUG rejected negotiated version version=3.0 negotiated=2.2",
+                "DEBUG WSACleanup succeeded",
+            ]
+        );
+        assert_eq!(lines[3], "record negotiated=1.1");
+    }
+}
+
+#[cfg(all(test, feature = "log"))]
+mod log_tests {
+    use crate::{CleanupPolicy, Simulated, WsaError, WsaInitializer};
+    use log::{Level, Log, Metadata, Record};
+    use std::{
+        sync::{Mutex, Once},
+        thread::{self, ThreadId},
+    };
+
+    /// A logger writing down every record along with the thread it came from
+    struct Capture(Mutex<Vec<(ThreadId, Level, String)>>);
+
+    static CAPTURE: Capture = Capture(Mutex::new(Vec::new()));
+
+    impl Log for Capture {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record<'_>) {
+            let line = (
+                thread::current().id(),
+                record.level(),
+                record.args().to_string(),
+            );
+            self.0.lock().unwrap().push(line);
+        }
+
+        fn flush(&self) {}
+    }
+
+    /// The records logged by this thread so far
+    fn records() -> Vec<(Level, String)> {
+        static INSTALL: Once = Once::new();
+        INSTALL.call_once(|| {
+            log::set_logger(&CAPTURE).unwrap();
+            log::set_max_level(log::LevelFilter::Trace);
+        });
+        let current = thread::current().id();
+        CAPTURE
+            .0
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(thread, ..)| *thread == current)
+            .map(|(_, level, line)| (*level, line.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn logs_startup_and_cleanup() {
+        assert!(records().is_empty());
+        let simulated = Simulated::new();
+        simulated.fail_startup(WsaError::TasksLimitReached);
+        let init = || WsaInitializer::with_backend(simulated.clone()).init();
+        assert!(init().is_err());
+        let wsa = init().unwrap();
+        simulated.fail_cleanup(WsaError::NotInitialised);
+        assert!(wsa.try_clean().is_err());
+        init().unwrap().clean();
+
+        let expected = [
+            (Level::Debug, "starting up WSA 2.2"),
+            (
+                Level::Warn,
+                "WSAStartup asking for 2.2 failed with TasksLimitReached (10067)",
+            ),
+            (Level::Debug, "starting up WSA 2.2"),
+            (
+                Level::Debug,
+                "WSA 2.2 started up, Simulated WinSock supports up to 2.2",
+            ),
+            (Level::Warn, "WSACleanup failed with NotInitialised (10093)"),
+            (Level::Debug, "starting up WSA 2.2"),
+            (
+                Level::Debug,
+                "WSA 2.2 started up, Simulated WinSock supports up to 2.2",
+            ),
+            (Level::Debug, "WSACleanup succeeded"),
+        ];
+        let records = records();
+        let (problems, records): (Vec<_>, Vec<_>) = records
+            .iter()
+            .map(|(level, line)| (*level, line.as_str()))
+            .partition(|(_, line)| line.starts_with("WSA guard"));
+        assert_eq!(records, expected);
+        if cfg!(debug_assertions) {
+            assert_eq!(problems.len(), 1);
+            assert_eq!(problems[0].0, Level::Warn);
+            assert!(problems[0]
+                .1
+                .starts_with("WSA guard cleaned up more than initialized: Wsa created at src/"));
+        }
+    }
+
+    #[test]
+    fn logs_cleanup_policy() {
+        assert!(records().is_empty());
+        let simulated = Simulated::new();
+        let mut initializer = WsaInitializer::with_backend(simulated.clone());
+        initializer.cleanup_policy(CleanupPolicy::Log);
+        let raii = initializer.init().unwrap().raii();
+        simulated.fail_cleanup(WsaError::NetworkDown);
+        drop(raii);
+        let err = WsaError::NetworkDown;
+        assert!(records().contains(&(Level::Error, format!("failed to clean up WSA: {err}"))));
+    }
+}
@@ -0,0 +1,325 @@
+//! This module tracks every [`Wsa`](crate::Wsa) and [`WsaRaii`](crate::WsaRaii) in debug builds,
+//! to find initializations that are never cleaned up and cleanups without a matching initialization.
+//!
+//! Each guard records where it was created, and a backtrace when `RUST_BACKTRACE` or
+//! `RUST_LIB_BACKTRACE` is set. In release builds nothing is tracked and everything here is empty.
+//!
+//! Problems are reported through `tracing` and `log` when those features are enabled,
+//! and written to stderr at exit once [`report_at_exit`] is called.
+
+use crate::{sys, trace};
+use std::{
+    backtrace::{Backtrace, BacktraceStatus},
+    collections::{BTreeMap, VecDeque},
+    fmt::{Display, Formatter, Result as FmtResult, Write},
+    panic::Location,
+    sync::{Arc, Mutex, MutexGuard, Once, PoisonError},
+};
+
+/// Which guard is holding WSA initialized
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GuardKind {
+    /// A [`Wsa`](crate::Wsa), which has to be cleaned up explicitly
+    Wsa,
+    /// A [`WsaRaii`](crate::WsaRaii), which cleans up on drop
+    WsaRaii,
+}
+
+/// Where a guard was created
+#[derive(Debug, Clone)]
+pub struct Site {
+    kind: GuardKind,
+    location: &'static Location<'static>,
+    backtrace: Option<Arc<Backtrace>>,
+}
+
+/// Something that went wrong with a guard
+#[derive(Debug, Clone)]
+pub enum Problem {
+    /// A [`Wsa`](crate::Wsa) was dropped without `.raii()`, `.clean()` or `.try_clean()`,
+    /// so WSA stays initialized
+    Leaked(Site),
+    /// Cleaning up failed with [`WsaError::NotInitialised`](crate::WsaError::NotInitialised),
+    /// so WSA was cleaned up more times than it was initialized
+    Unbalanced(Site),
+}
+
+/// How many problems are kept, once there are more the oldest ones are dropped
+pub const MAX_PROBLEMS: usize = 64;
+
+struct Registry {
+    next: u64,
+    outstanding: BTreeMap<u64, Site>,
+    problems: VecDeque<Problem>,
+}
+
+impl Registry {
+    /// Keeps `problem`, dropping the oldest one when there are already [`MAX_PROBLEMS`]
+    fn record(&mut self, problem: Problem) {
+        if self.problems.len() == MAX_PROBLEMS {
+            self.problems.pop_front();
+        }
+        self.problems.push_back(problem);
+    }
+}
+
+static REGISTRY: Mutex<Registry> = Mutex::new(Registry {
+    next: 0,
+    outstanding: BTreeMap::new(),
+    problems: VecDeque::new(),
+});
+
+fn registry() -> MutexGuard<'static, Registry> {
+    REGISTRY.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl Site {
+    /// The kind of guard currently holding the initialization
+    #[must_use]
+    pub const fn kind(&self) -> GuardKind {
+        self.kind
+    }
+
+    /// Where WSA was initialized
+    #[must_use]
+    pub const fn location(&self) -> &'static Location<'static> {
+        self.location
+    }
+
+    /// How WSA came to be initialized, if backtraces were enabled
+    #[must_use]
+    pub fn backtrace(&self) -> Option<&Backtrace> {
+        self.backtrace.as_deref()
+    }
+}
+
+impl Display for Site {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{:?} created at {}", self.kind, self.location)?;
+        if let Some(backtrace) = &self.backtrace {
+            write!(f, "\n{backtrace}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Leaked(site) => write!(f, "dropped without cleaning up: {site}"),
+            Self::Unbalanced(site) => write!(f, "cleaned up more than initialized: {site}"),
+        }
+    }
+}
+
+/// The guards currently keeping WSA initialized, oldest first
+#[must_use]
+pub fn outstanding() -> Vec<Site> {
+    registry().outstanding.values().cloned().collect()
+}
+
+/// The last [`MAX_PROBLEMS`] problems found, oldest first
+#[must_use]
+pub fn problems() -> Vec<Problem> {
+    registry().problems.iter().cloned().collect()
+}
+
+/// A human readable report of the outstanding guards and problems, empty if there are none
+#[must_use]
+pub fn report() -> String {
+    let mut report = String::new();
+    for site in outstanding() {
+        let _ = writeln!(report, "outstanding: {site}");
+    }
+    for problem in problems() {
+        let _ = writeln!(report, "{problem}");
+    }
+    report
+}
+
+/// Registers a hook that writes the [`report`] to stderr when the process exits, if it isn't empty.
+/// Registering more than once has no effect
+pub fn report_at_exit() {
+    extern "C" fn print() {
+        let report = report();
+        if !report.is_empty() {
+            eprint!("WSA guards left at exit:\n{report}");
+        }
+    }
+
+    static REGISTER: Once = Once::new();
+    REGISTER.call_once(|| sys::at_exit(print));
+}
+
+/// The registration of a guard, removed on drop.\
+/// Dropping it while it still belongs to a [`Wsa`](crate::Wsa) flags a leak
+pub(crate) struct Tracked(Option<u64>);
+
+impl Tracked {
+    /// Registers a [`Wsa`](crate::Wsa) created at `location`
+    pub(crate) fn new(location: &'static Location<'static>) -> Self {
+        if !cfg!(debug_assertions) {
+            return Self(None);
+        }
+        let backtrace = Backtrace::capture();
+        let site = Site {
+            kind: GuardKind::Wsa,
+            location,
+            backtrace: (backtrace.status() == BacktraceStatus::Captured)
+                .then(|| Arc::new(backtrace)),
+        };
+        let mut registry = registry();
+        let id = registry.next;
+        registry.next += 1;
+        registry.outstanding.insert(id, site);
+        drop(registry);
+        Self(Some(id))
+    }
+
+    /// Hands the registration over to a [`WsaRaii`](crate::WsaRaii)
+    pub(crate) fn raii(self) -> Self {
+        if let Some(id) = self.0 {
+            if let Some(site) = registry().outstanding.get_mut(&id) {
+                site.kind = GuardKind::WsaRaii;
+            }
+        }
+        self
+    }
+
+    /// Flags WSA being cleaned up more than it was initialized
+    pub(crate) fn unbalanced(&self) {
+        if let Some(id) = self.0 {
+            let mut registry = registry();
+            if let Some(site) = registry.outstanding.get(&id).cloned() {
+                let problem = Problem::Unbalanced(site);
+                registry.record(problem.clone());
+                drop(registry);
+                trace::problem(&problem);
+            }
+        }
+    }
+
+    /// Removes the registration, as WSA was cleaned up or its cleanup is handled elsewhere
+    pub(crate) fn release(mut self) {
+        if let Some(id) = self.0.take() {
+            registry().outstanding.remove(&id);
+        }
+    }
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        let Some(id) = self.0.take() else { return };
+        let mut registry = registry();
+        let Some(site) = registry.outstanding.remove(&id) else {
+            return;
+        };
+        if site.kind == GuardKind::Wsa {
+            let problem = Problem::Leaked(site);
+            registry.record(problem.clone());
+            drop(registry);
+            trace::problem(&problem);
+        }
+    }
+}
+
+#[cfg(all(test, debug_assertions))]
+mod tests {
+    use super::{outstanding, problems, report, GuardKind, Problem, Registry, Site, MAX_PROBLEMS};
+    use crate::{Backend, Simulated, Wsa, WsaInitializer};
+    use std::{
+        collections::{BTreeMap, VecDeque},
+        panic::Location,
+    };
+
+    /// Initializes WSA, along with where the guard should be recorded as created
+    #[track_caller]
+    fn init(simulated: &Simulated) -> (Wsa<Simulated>, &'static Location<'static>) {
+        let wsa = WsaInitializer::with_backend(simulated.clone()).init();
+        (wsa.unwrap(), Location::caller())
+    }
+
+    fn kind(site: &Location) -> Option<GuardKind> {
+        outstanding()
+            .into_iter()
+            .find(|outstanding| outstanding.location() == site)
+            .map(|outstanding| outstanding.kind())
+    }
+
+    #[test]
+    fn tracks_outstanding_guards() {
+        let simulated = Simulated::new();
+        let (wsa, site) = init(&simulated);
+        assert_eq!(kind(site), Some(GuardKind::Wsa));
+
+        let raii = wsa.raii();
+        assert_eq!(kind(site), Some(GuardKind::WsaRaii));
+        assert!(report().contains(&format!("outstanding: WsaRaii created at {site}")));
+        drop(raii);
+        assert_eq!(kind(site), None);
+        assert!(!problems().iter().any(|problem| match problem {
+            Problem::Leaked(other) | Problem::Unbalanced(other) => other.location() == site,
+        }));
+    }
+
+    #[test]
+    fn flags_leaked_wsa() {
+        let simulated = Simulated::new();
+        let (wsa, site) = init(&simulated);
+        drop(wsa);
+        assert_eq!(simulated.startups(), 1);
+        assert_eq!(kind(site), None);
+        assert!(problems().iter().any(|problem| matches!(
+            problem,
+            Problem::Leaked(leaked) if leaked.location() == site
+        )));
+        assert!(report().contains(&format!(
+            "dropped without cleaning up: Wsa created at {site}"
+        )));
+    }
+
+    #[test]
+    fn flags_unbalanced_cleanup() {
+        let simulated = Simulated::new();
+        let (wsa, site) = init(&simulated);
+        simulated.cleanup().unwrap();
+        assert!(wsa.try_clean().is_err());
+        assert!(problems().iter().any(|problem| matches!(
+            problem,
+            Problem::Unbalanced(unbalanced) if unbalanced.location() == site
+        )));
+
+        let (wsa, site) = init(&simulated);
+        let raii = wsa.raii();
+        simulated.cleanup().unwrap();
+        drop(raii);
+        assert!(problems().iter().any(|problem| matches!(
+            problem,
+            Problem::Unbalanced(unbalanced) if unbalanced.location() == site
+        )));
+    }
+
+    #[test]
+    fn keeps_last_problems() {
+        let mut registry = Registry {
+            next: 0,
+            outstanding: BTreeMap::new(),
+            problems: VecDeque::new(),
+        };
+        let first = Location::caller();
+        let site = |location| Site {
+            kind: GuardKind::Wsa,
+            location,
+            backtrace: None,
+        };
+        registry.record(Problem::Leaked(site(first)));
+        for _ in 0..MAX_PROBLEMS {
+            registry.record(Problem::Unbalanced(site(Location::caller())));
+        }
+        assert_eq!(registry.problems.len(), MAX_PROBLEMS);
+        assert!(registry
+            .problems
+            .iter()
+            .all(|problem| matches!(problem, Problem::Unbalanced(_))));
+    }
+}
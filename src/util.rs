@@ -1,10 +1,12 @@
 //! This module holds functions that allow one to really easily start up WSA
 
-use crate::{Result, Wsa, WsaInitializer};
+use crate::{sys, Result, Scoped, SharedWsa, Wsa, WsaInfo, WsaInitializer};
+use std::sync::{Mutex, MutexGuard, Once, PoisonError};
 
 /// Initialize WSA with default zeroed options and version 2.2
 /// # Errors
-/// This function will return a [`WsaError`] when `WSAStartup` fails
+/// This function will return a [`WsaError`](crate::WsaError) when `WSAStartup` fails
+#[track_caller]
 pub fn try_wsa_startup() -> Result<Wsa> {
     WsaInitializer::default().init()
 }
@@ -12,6 +14,115 @@ pub fn try_wsa_startup() -> Result<Wsa> {
 /// Initialize WSA with default zeroed options and version 2.2
 /// # Panics
 /// This may panic if `WSAStartup` fails
+#[track_caller]
 pub fn wsa_startup() -> Wsa {
     try_wsa_startup().unwrap()
 }
+
+/// Initializes WSA with version 2.2, runs `f` with the [`WsaInfo`] it reported, then cleans WSA up,
+/// even if `f` panics. See [`WsaInitializer::with_wsa`]
+/// # Errors
+/// This function will return a [`WsaError`](crate::WsaError) when `WSAStartup` fails
+#[track_caller]
+pub fn with_wsa<R>(f: impl FnOnce(&WsaInfo) -> R) -> Result<Scoped<R>> {
+    WsaInitializer::default().with_wsa(f)
+}
+
+/// The result of the first [`ensure_wsa`] call, holding the handle that keeps WSA initialized for it
+/// along with the info its startup reported.\
+/// Taken by the at-exit cleanup, so later calls start WSA up again
+static GLOBAL: Mutex<Option<Result<(SharedWsa, WsaInfo)>>> = Mutex::new(None);
+
+fn global() -> MutexGuard<'static, Option<Result<(SharedWsa, WsaInfo)>>> {
+    GLOBAL.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Makes sure WSA is initialized, with version 2.2, for the rest of the process.
+///
+/// Only the first call initializes WSA, every call returns the result of that first initialization,
+/// with the [`WsaInfo`] it reported.
+/// WSA stays initialized until the process exits, see [`cleanup_wsa_at_exit`] to clean it up then.
+/// Calls made after that cleanup, from other at-exit hooks, initialize WSA anew and leave it to the OS.
+/// # Errors
+/// This function will return the [`WsaError`](crate::WsaError) the first call failed with
+pub fn ensure_wsa() -> Result<WsaInfo> {
+    global()
+        .get_or_insert_with(|| {
+            let handle = SharedWsa::acquire()?;
+            let info = handle.info();
+            Ok((handle, info))
+        })
+        .as_ref()
+        .map(|(_, info)| info.clone())
+        .map_err(|err| *err)
+}
+
+/// Registers a hook that releases the WSA initialization made by [`ensure_wsa`] when the process exits.
+/// Registering more than once has no effect
+pub fn cleanup_wsa_at_exit() {
+    extern "C" fn release() {
+        release_global();
+    }
+
+    static REGISTER: Once = Once::new();
+    REGISTER.call_once(|| sys::at_exit(release));
+}
+
+/// Releases the handle held for [`ensure_wsa`], if there is one, and forgets its result
+fn release_global() {
+    let handle = global().take();
+    drop(handle);
+}
+
+#[cfg(all(test, not(windows)))]
+mod tests {
+    use super::{ensure_wsa, release_global, with_wsa};
+    use crate::{sys, SharedWsa, Simulated, WsaInitializer, WsaVersion};
+
+    #[test]
+    fn with_wsa_initializes_around_closure() {
+        let _serial = sys::serial();
+        let scoped = with_wsa(|info| (info.version(), sys::startups())).unwrap();
+        assert_eq!(scoped.into_result(), Ok((WsaVersion::V2_2, 1)));
+        assert_eq!(sys::startups(), 0);
+    }
+
+    #[test]
+    fn ensure_initializes_once() {
+        let _serial = sys::serial();
+        let info = ensure_wsa().unwrap();
+        assert_eq!(info.version(), WsaVersion::V2_2);
+        assert_eq!(ensure_wsa(), Ok(info));
+        assert_eq!((SharedWsa::count(), sys::startups()), (1, 1));
+
+        let other = SharedWsa::acquire().unwrap();
+        release_global();
+        assert_eq!((SharedWsa::count(), sys::startups()), (1, 1));
+        drop(other);
+        assert_eq!((SharedWsa::count(), sys::startups()), (0, 0));
+    }
+
+    #[test]
+    fn ensure_after_release_starts_up_again() {
+        let _serial = sys::serial();
+        assert!(ensure_wsa().is_ok());
+        release_global();
+        assert_eq!((SharedWsa::count(), sys::startups()), (0, 0));
+
+        assert!(ensure_wsa().is_ok());
+        assert_eq!((SharedWsa::count(), sys::startups()), (1, 1));
+        release_global();
+        assert_eq!((SharedWsa::count(), sys::startups()), (0, 0));
+    }
+
+    #[test]
+    fn ensure_reports_the_shared_startup() {
+        let _serial = sys::serial();
+        let simulated = Simulated::supporting(WsaVersion::new(1, 0), WsaVersion::new(2, 0));
+        let first = WsaInitializer::with_backend(simulated).shared().unwrap();
+        assert_eq!(ensure_wsa().unwrap().version(), WsaVersion::new(2, 0));
+        drop(first);
+        release_global();
+        assert_eq!(SharedWsa::count(), 0);
+    }
+}
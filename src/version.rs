@@ -0,0 +1,172 @@
+//! This module holds [`WsaVersion`], a typed Windows Sockets version
+
+use std::{
+    error::Error,
+    fmt::{Display, Formatter, Result as FmtResult},
+    num::ParseIntError,
+    str::FromStr,
+};
+
+/// A Windows Sockets version, ordered by major and then minor version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct WsaVersion {
+    pub major: u8,
+    pub minor: u8,
+}
+
+impl WsaVersion {
+    /// Windows Sockets 2.2, the version every supported windows offers
+    pub const V2_2: Self = Self::new(2, 2);
+
+    #[must_use]
+    pub const fn new(major: u8, minor: u8) -> Self {
+        Self { major, minor }
+    }
+
+    /// The version in the format `WSAStartup` takes, major version in the low byte
+    #[must_use]
+    pub const fn to_word(self) -> u16 {
+        u16::from_le_bytes([self.major, self.minor])
+    }
+
+    /// Reads a version in the format `WSAStartup` takes, major version in the low byte
+    #[must_use]
+    pub const fn from_word(word: u16) -> Self {
+        let [major, minor] = word.to_le_bytes();
+        Self { major, minor }
+    }
+}
+
+impl Default for WsaVersion {
+    fn default() -> Self {
+        Self::V2_2
+    }
+}
+
+impl From<u16> for WsaVersion {
+    fn from(word: u16) -> Self {
+        Self::from_word(word)
+    }
+}
+
+impl From<WsaVersion> for u16 {
+    fn from(version: WsaVersion) -> Self {
+        version.to_word()
+    }
+}
+
+impl From<(u8, u8)> for WsaVersion {
+    fn from((major, minor): (u8, u8)) -> Self {
+        Self { major, minor }
+    }
+}
+
+impl Display for WsaVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// An error returned when parsing a [`WsaVersion`] out of a `"major.minor"` string fails
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseVersionError {
+    /// There is no `.` separating the major and minor versions
+    MissingSeparator,
+    /// The major or minor version isn't a number between 0 and 255
+    InvalidNumber(ParseIntError),
+}
+
+impl Error for ParseVersionError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::MissingSeparator => None,
+            Self::InvalidNumber(err) => Some(err),
+        }
+    }
+}
+
+impl Display for ParseVersionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::MissingSeparator => write!(f, "expected a version formatted as \"major.minor\""),
+            Self::InvalidNumber(err) => write!(f, "invalid version number: {err}"),
+        }
+    }
+}
+
+impl From<ParseIntError> for ParseVersionError {
+    fn from(err: ParseIntError) -> Self {
+        Self::InvalidNumber(err)
+    }
+}
+
+impl FromStr for WsaVersion {
+    type Err = ParseVersionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (major, minor) = s
+            .trim()
+            .split_once('.')
+            .ok_or(ParseVersionError::MissingSeparator)?;
+        Ok(Self {
+            major: major.parse()?,
+            minor: minor.parse()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ParseVersionError, WsaVersion};
+
+    #[test]
+    fn word_layout() {
+        assert_eq!(WsaVersion::new(2, 1).to_word(), 0x0102);
+        assert_eq!(WsaVersion::from_word(0x0102), WsaVersion::new(2, 1));
+        assert_eq!(
+            WsaVersion::from(u16::from(WsaVersion::V2_2)),
+            WsaVersion::V2_2
+        );
+    }
+
+    #[test]
+    fn ordering() {
+        let mut versions = vec![
+            WsaVersion::new(2, 0),
+            WsaVersion::new(1, 1),
+            WsaVersion::new(2, 2),
+            WsaVersion::new(1, 0),
+        ];
+        versions.sort();
+        assert_eq!(
+            versions,
+            [(1, 0), (1, 1), (2, 0), (2, 2)].map(WsaVersion::from)
+        );
+        assert!(WsaVersion::new(1, 9) < WsaVersion::new(2, 0));
+    }
+
+    #[test]
+    fn parsing() {
+        assert_eq!("2.2".parse(), Ok(WsaVersion::V2_2));
+        assert_eq!(" 1.1 ".parse(), Ok(WsaVersion::new(1, 1)));
+        assert_eq!(
+            "2".parse::<WsaVersion>(),
+            Err(ParseVersionError::MissingSeparator)
+        );
+        assert!(matches!(
+            "2.256".parse::<WsaVersion>(),
+            Err(ParseVersionError::InvalidNumber(_))
+        ));
+        assert!(matches!(
+            "two.two".parse::<WsaVersion>(),
+            Err(ParseVersionError::InvalidNumber(_))
+        ));
+    }
+
+    #[test]
+    fn display_round_trips() {
+        let version = WsaVersion::new(1, 1);
+        assert_eq!(version.to_string(), "1.1");
+        assert_eq!(version.to_string().parse(), Ok(version));
+    }
+}
@@ -0,0 +1,203 @@
+//! Tests calling the C API the way a C or C++ component would
+#![cfg(feature = "capi")]
+
+use object::{Object, ObjectSymbol};
+use std::{
+    env, fs,
+    os::raw::c_char,
+    path::Path,
+    process::Command,
+    ptr,
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+use wsa_startup::{capi, SharedWsa, Simulated, WsaError, WsaInitializer};
+
+extern "C" {
+    fn wsa_startup_acquire() -> i32;
+    fn wsa_startup_acquire_version(major: u8, minor: u8) -> i32;
+    fn wsa_startup_release() -> i32;
+    fn wsa_startup_refcount() -> usize;
+    fn wsa_startup_last_error() -> i32;
+    fn wsa_startup_last_error_message(buffer: *mut c_char, size: usize) -> usize;
+}
+
+/// Serializes the tests, as they share the process wide reference count
+fn serial() -> MutexGuard<'static, ()> {
+    static LOCK: Mutex<()> = Mutex::new(());
+    LOCK.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// The message of the last error, read through a buffer of `size` bytes
+fn last_error_message(size: usize) -> (usize, Vec<u8>) {
+    let mut buffer = vec![0xff_u8; size];
+    let len = unsafe { wsa_startup_last_error_message(buffer.as_mut_ptr().cast(), size) };
+    (len, buffer)
+}
+
+#[test]
+fn shares_rust_initialization() {
+    let _serial = serial();
+    let simulated = Simulated::new();
+    let rust = WsaInitializer::with_backend(simulated.clone())
+        .shared()
+        .unwrap();
+
+    unsafe {
+        assert_eq!(wsa_startup_acquire(), 0);
+        assert_eq!(wsa_startup_acquire_version(1, 1), 0);
+        assert_eq!(wsa_startup_refcount(), 3);
+        assert_eq!(simulated.startups(), 1);
+
+        drop(rust);
+        assert_eq!(wsa_startup_release(), 0);
+        assert_eq!((wsa_startup_refcount(), simulated.startups()), (1, 1));
+        assert_eq!(wsa_startup_release(), 0);
+        assert_eq!((wsa_startup_refcount(), simulated.startups()), (0, 0));
+        assert_eq!(wsa_startup_last_error(), 0);
+    }
+}
+
+#[test]
+fn rust_shares_c_initialization() {
+    let _serial = serial();
+    let simulated = Simulated::new();
+    capi::set_backend(simulated.clone());
+    unsafe {
+        assert_eq!(wsa_startup_acquire_version(2, 0), 0);
+    }
+    let rust = WsaInitializer::with_backend(Simulated::new())
+        .shared()
+        .unwrap();
+    assert_eq!((SharedWsa::count(), simulated.startups()), (2, 1));
+
+    unsafe {
+        assert_eq!(wsa_startup_release(), 0);
+    }
+    assert_eq!((SharedWsa::count(), simulated.startups()), (1, 1));
+    drop(rust);
+    assert_eq!((SharedWsa::count(), simulated.startups()), (0, 0));
+}
+
+#[test]
+fn reports_failed_acquire() {
+    let _serial = serial();
+    let simulated = Simulated::new();
+    simulated.fail_startup(WsaError::SystemNotReady);
+    capi::set_backend(simulated.clone());
+
+    unsafe {
+        assert_eq!(wsa_startup_acquire(), 10091);
+        assert_eq!(wsa_startup_last_error(), 10091);
+    }
+    assert_eq!((SharedWsa::count(), simulated.startups()), (0, 0));
+    let message = WsaError::SystemNotReady.to_string();
+    let (len, buffer) = last_error_message(128);
+    assert_eq!(&buffer[..=len], [message.as_bytes(), b"\0"].concat());
+
+    unsafe {
+        assert_eq!(wsa_startup_acquire_version(1, 1), 0);
+        assert_eq!(wsa_startup_last_error(), 0);
+        assert_eq!(simulated.startups(), 1);
+        assert_eq!(wsa_startup_release(), 0);
+    }
+    assert_eq!(simulated.startups(), 0);
+}
+
+#[test]
+fn reports_failed_cleanup() {
+    let _serial = serial();
+    let simulated = Simulated::new();
+    capi::set_backend(simulated.clone());
+
+    unsafe {
+        assert_eq!(wsa_startup_acquire(), 0);
+        assert_eq!(wsa_startup_acquire(), 0);
+        simulated.fail_cleanup(WsaError::NetworkDown);
+        // Only the last handle cleans up
+        assert_eq!(wsa_startup_release(), 0);
+        assert_eq!(wsa_startup_release(), 10050);
+        assert_eq!(wsa_startup_last_error(), 10050);
+    }
+    assert_eq!((SharedWsa::count(), simulated.startups()), (0, 1));
+    let message = WsaError::NetworkDown.to_string();
+    let (len, buffer) = last_error_message(128);
+    assert_eq!(&buffer[..=len], [message.as_bytes(), b"\0"].concat());
+}
+
+#[test]
+fn reports_unbalanced_release() {
+    let _serial = serial();
+    let simulated = Simulated::new();
+    let rust = WsaInitializer::with_backend(simulated.clone())
+        .shared()
+        .unwrap();
+
+    // Handles acquired from Rust can't be released through C
+    unsafe {
+        assert_eq!(wsa_startup_release(), 10093);
+        assert_eq!(wsa_startup_last_error(), 10093);
+    }
+    assert_eq!((SharedWsa::count(), simulated.startups()), (1, 1));
+
+    let message = WsaError::NotInitialised.to_string();
+    let (len, buffer) = last_error_message(128);
+    assert_eq!(len, message.len());
+    assert_eq!(&buffer[..=len], [message.as_bytes(), b"\0"].concat());
+
+    let (len, buffer) = last_error_message(6);
+    assert_eq!((len, buffer.as_slice()), (message.len(), &b"WSANO\0"[..]));
+    assert_eq!(
+        unsafe { wsa_startup_last_error_message(ptr::null_mut(), 0) },
+        message.len()
+    );
+
+    unsafe {
+        assert_eq!(wsa_startup_acquire(), 0);
+        assert_eq!(wsa_startup_last_error(), 0);
+        assert_eq!(wsa_startup_release(), 0);
+    }
+    assert_eq!(last_error_message(4), (0, b"\0\xff\xff\xff".to_vec()));
+    drop(rust);
+}
+
+/// Every function the C API exports
+const EXPORTS: &[&str] = &[
+    "wsa_startup_acquire",
+    "wsa_startup_acquire_version",
+    "wsa_startup_release",
+    "wsa_startup_refcount",
+    "wsa_startup_last_error",
+    "wsa_startup_last_error_message",
+];
+
+#[test]
+fn builds_cdylib_as_documented() {
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("capi");
+    let status = Command::new(env::var_os("CARGO").unwrap_or_else(|| "cargo".into()))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(["rustc", "--lib", "--quiet", "--features", "capi"])
+        .args(["--crate-type", "cdylib"])
+        .arg("--target-dir")
+        .arg(&target_dir)
+        .status()
+        .expect("cargo should be runnable");
+    assert!(status.success());
+
+    let name = format!(
+        "{}wsa_startup{}",
+        env::consts::DLL_PREFIX,
+        env::consts::DLL_SUFFIX
+    );
+    let data = fs::read(target_dir.join("debug").join(name)).unwrap();
+    let library = object::File::parse(&*data).unwrap();
+    let mut exports: Vec<_> = library
+        .dynamic_symbols()
+        .filter(|symbol| symbol.is_definition() && symbol.is_global())
+        .filter_map(|symbol| symbol.name().ok())
+        .filter(|name| name.starts_with("wsa_startup_"))
+        .collect();
+    exports.sort_unstable();
+    let mut expected = EXPORTS.to_vec();
+    expected.sort_unstable();
+    assert_eq!(exports, expected);
+}
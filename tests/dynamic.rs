@@ -0,0 +1,205 @@
+//! Tests for the `Dynamic` backend, loading a stub Winsock built from `tests/dynamic/ws2_stub.c`
+#![cfg(all(feature = "dynamic", unix))]
+
+use libloading::Library;
+use std::{
+    os::raw::c_int,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{Mutex, MutexGuard, OnceLock, PoisonError},
+};
+use wsa_startup::{
+    protocol::{ProtocolChain, ServiceFlags},
+    Backend, Dynamic, LoadError, SharedWsa, WsaError, WsaInitializer, WsaVersion,
+};
+
+/// Builds the stub with the C compiler, with `defines` set, into a library named `name`
+fn build(name: &str, defines: &[&str]) -> PathBuf {
+    let source = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/dynamic/ws2_stub.c");
+    let output = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let status = Command::new(option_env!("CC").unwrap_or("cc"))
+        .args(["-shared", "-fPIC", "-o"])
+        .arg(&output)
+        .args(defines.iter().map(|define| format!("-D{define}")))
+        .arg(&source)
+        .status()
+        .expect("a C compiler is needed to build the stub Winsock");
+    assert!(status.success(), "building the stub Winsock failed");
+    output
+}
+
+/// The stub exporting every function, built once
+fn stub_path() -> &'static Path {
+    static STUB: OnceLock<PathBuf> = OnceLock::new();
+    STUB.get_or_init(|| build("libws2_stub.so", &[]))
+}
+
+/// The stub's own state, which every load of it shares
+struct Stub(Library);
+
+impl Stub {
+    fn open() -> Self {
+        Self(unsafe { Library::new(stub_path()) }.unwrap())
+    }
+
+    fn fail_next_startup(&self, err: WsaError) {
+        let fail = unsafe {
+            self.0
+                .get::<unsafe extern "C" fn(c_int)>(b"stub_fail_next_startup")
+                .unwrap()
+        };
+        unsafe { fail(err.code()) }
+    }
+
+    fn startups(&self) -> c_int {
+        let startups = unsafe {
+            self.0
+                .get::<unsafe extern "C" fn() -> c_int>(b"stub_startups")
+                .unwrap()
+        };
+        unsafe { startups() }
+    }
+}
+
+/// Serializes the tests, as they share the stub's state
+fn serial() -> MutexGuard<'static, ()> {
+    static LOCK: Mutex<()> = Mutex::new(());
+    LOCK.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[test]
+fn starts_up_through_loaded_library() {
+    let _serial = serial();
+    let stub = Stub::open();
+    let dynamic = Dynamic::load(stub_path()).unwrap();
+    assert_eq!(dynamic.name(), stub_path());
+
+    let mut initializer = WsaInitializer::with_backend(dynamic.clone());
+    initializer.version((3, 0));
+    let wsa = initializer.init().unwrap();
+    assert_eq!(wsa.info().version(), WsaVersion::V2_2);
+    assert_eq!(wsa.info().description(), "WinSock stub");
+    assert_eq!(wsa.info().system_status(), "Loaded");
+    assert_eq!(stub.startups(), 1);
+
+    assert_eq!(wsa.try_clean(), Ok(()));
+    assert_eq!(stub.startups(), 0);
+    assert_eq!(dynamic.cleanup(), Err(WsaError::NotInitialised));
+    assert_eq!(dynamic.last_error(), Some(WsaError::NotInitialised));
+}
+
+#[test]
+fn sets_last_error() {
+    let _serial = serial();
+    let dynamic = Dynamic::load(stub_path()).unwrap();
+    dynamic.set_last_error(Some(WsaError::WouldBlock));
+    assert_eq!(dynamic.last_error(), Some(WsaError::WouldBlock));
+    dynamic.set_last_error(None);
+    assert_eq!(dynamic.last_error(), None);
+}
+
+#[test]
+fn reports_startup_failures() {
+    let _serial = serial();
+    let stub = Stub::open();
+    let dynamic = Dynamic::load(stub_path()).unwrap();
+
+    stub.fail_next_startup(WsaError::SystemNotReady);
+    let wsa = WsaInitializer::with_backend(dynamic.clone()).init();
+    assert_eq!(wsa.err(), Some(WsaError::SystemNotReady));
+
+    let mut initializer = WsaInitializer::with_backend(dynamic);
+    initializer.version((0, 9));
+    assert_eq!(
+        initializer.init().err(),
+        Some(WsaError::VersionNotSupported)
+    );
+    assert_eq!(stub.startups(), 0);
+}
+
+#[test]
+fn shares_loaded_library() {
+    let _serial = serial();
+    let stub = Stub::open();
+    let dynamic = Dynamic::load(stub_path()).unwrap();
+    let first = WsaInitializer::with_backend(dynamic.clone())
+        .shared()
+        .unwrap();
+    let second = WsaInitializer::with_backend(dynamic).shared().unwrap();
+    assert_eq!((SharedWsa::count(), stub.startups()), (2, 1));
+
+    drop(first);
+    drop(second);
+    assert_eq!(stub.startups(), 0);
+}
+
+#[test]
+fn lists_protocols() {
+    let _serial = serial();
+    let dynamic = Dynamic::load(stub_path()).unwrap();
+    let wsa = WsaInitializer::with_backend(dynamic).init().unwrap();
+    let protocols = wsa.protocols().unwrap();
+    assert_eq!(protocols.len(), 1);
+    let tcp = &protocols[0];
+    assert_eq!(tcp.name(), "Stub [TCP/IP]");
+    assert_eq!(tcp.catalog_entry_id(), 1001);
+    assert_eq!(*tcp.chain(), ProtocolChain::Base);
+    assert_eq!(
+        (tcp.address_family(), tcp.socket_type(), tcp.protocol()),
+        (2, 1, 6)
+    );
+    assert!(tcp
+        .service_flags()
+        .contains(ServiceFlags::GUARANTEED_DELIVERY));
+    assert_eq!(
+        tcp.provider_id().to_string(),
+        "{E70F1AA0-AB8B-11CF-8CA3-00805F48A192}"
+    );
+    wsa.clean();
+}
+
+#[test]
+fn protocols_are_optional() {
+    let _serial = serial();
+    let stub = build("libws2_stub_without_enum.so", &["WITHOUT_ENUM_PROTOCOLS"]);
+    let dynamic = Dynamic::load(stub).unwrap();
+    assert_eq!(dynamic.protocols(), Err(WsaError::OperationNotSupported));
+}
+
+#[test]
+fn missing_library() {
+    let missing = Path::new(env!("CARGO_TARGET_TMPDIR")).join("libws2_missing.so");
+    let err = Dynamic::load(&missing).unwrap_err();
+    assert!(
+        matches!(&err, LoadError::Library { name, .. } if name == missing.as_os_str()),
+        "{:?}",
+        err
+    );
+    assert_eq!(
+        err.to_string(),
+        format!("couldn't load Winsock from {}", missing.display())
+    );
+    assert!(std::error::Error::source(&err).is_some());
+}
+
+#[test]
+fn missing_symbol() {
+    static STUB: OnceLock<PathBuf> = OnceLock::new();
+    let stub = STUB.get_or_init(|| build("libws2_stub_without_cleanup.so", &["WITHOUT_CLEANUP"]));
+    let err = Dynamic::load(stub).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            LoadError::Symbol {
+                symbol: "WSACleanup",
+                ..
+            }
+        ),
+        "{:?}",
+        err
+    );
+    assert_eq!(
+        err.to_string(),
+        format!("{} doesn't export WSACleanup", stub.display())
+    );
+}
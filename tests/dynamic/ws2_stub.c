@@ -0,0 +1,98 @@
+// A stand-in for ws2_32, exporting the functions the Dynamic backend loads.
+// Built with WITHOUT_CLEANUP or WITHOUT_ENUM_PROTOCOLS defined, it lacks that function.
+
+#include <string.h>
+
+// The 64 bit layout of WSADATA, as the crate declares it outside of windows
+typedef struct {
+    unsigned short wVersion;
+    unsigned short wHighVersion;
+    unsigned short iMaxSockets;
+    unsigned short iMaxUdpDg;
+    char *lpVendorInfo;
+    char szDescription[257];
+    char szSystemStatus[129];
+} WSADATA;
+
+static int startups;
+static int next_failure;
+static int last_error;
+
+int WSAStartup(unsigned short version, WSADATA *data) {
+    if (next_failure) {
+        int failure = next_failure;
+        next_failure = 0;
+        return failure;
+    }
+    // The major version is the low byte, versions below 1.0 aren't supported
+    // and anything above 2.2 is negotiated down to it
+    unsigned major = version & 0xff, minor = version >> 8;
+    if (major < 1) {
+        return 10092;
+    }
+    memset(data, 0, sizeof(*data));
+    data->wVersion = major > 2 || (major == 2 && minor > 2) ? 0x0202 : version;
+    data->wHighVersion = 0x0202;
+    strcpy(data->szDescription, "WinSock stub");
+    strcpy(data->szSystemStatus, "Loaded");
+    startups++;
+    return 0;
+}
+
+#ifndef WITHOUT_CLEANUP
+int WSACleanup(void) {
+    if (startups == 0) {
+        last_error = 10093;
+        return -1;
+    }
+    startups--;
+    return 0;
+}
+#endif
+
+int WSAGetLastError(void) {
+    return last_error;
+}
+
+void WSASetLastError(int error) {
+    last_error = error;
+}
+
+#ifndef WITHOUT_ENUM_PROTOCOLS
+// Lists a single TCP/IP base protocol, asking for a large enough buffer first
+int WSAEnumProtocolsW(const int *protocols, void *buffer, unsigned *len) {
+    static const int fields[] = {2, 2, 16, 16, 1, 6};
+    static const unsigned char provider[16] = {0xa0, 0x1a, 0x0f, 0xe7, 0x8b, 0xab, 0xcf, 0x11,
+                                               0x8c, 0xa3, 0x00, 0x80, 0x5f, 0x48, 0xa1, 0x92};
+    static const char name[] = "Stub [TCP/IP]";
+    unsigned char *entry = buffer;
+    unsigned service_flags = 0x20066, catalog_entry_id = 1001;
+    int chain_len = 1;
+    (void) protocols;
+    if (*len < 628) {
+        *len = 628;
+        last_error = 10055;
+        return -1;
+    }
+    memset(entry, 0, 628);
+    memcpy(entry, &service_flags, 4);
+    memcpy(entry + 20, provider, 16);
+    memcpy(entry + 36, &catalog_entry_id, 4);
+    memcpy(entry + 40, &chain_len, 4);
+    memcpy(entry + 72, fields, sizeof(fields));
+    for (unsigned i = 0; name[i]; i++) {
+        entry[116 + 2 * i] = name[i];
+    }
+    return 1;
+}
+#endif
+
+// Makes the next WSAStartup fail with error
+void stub_fail_next_startup(int error) {
+    next_failure = error;
+}
+
+// How many startups weren't cleaned up yet
+int stub_startups(void) {
+    return startups;
+}
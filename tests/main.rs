@@ -0,0 +1,122 @@
+//! Tests for the `#[wsa_startup::main]` attribute
+#![cfg(feature = "macros")]
+
+use std::panic;
+use wsa_startup::{Simulated, WsaError, WsaVersion};
+
+#[wsa_startup::main(backend = simulated.clone())]
+fn startups(simulated: &Simulated) -> usize {
+    simulated.startups()
+}
+
+#[test]
+fn initialized_while_running() {
+    let simulated = Simulated::new();
+    assert_eq!(startups(&simulated), 1);
+    assert_eq!(simulated.startups(), 0);
+}
+
+#[wsa_startup::main(backend = simulated.clone())]
+fn panics(simulated: &Simulated) {
+    assert_eq!(simulated.startups(), 1);
+    panic!("in the body");
+}
+
+#[test]
+fn cleaned_up_on_unwind() {
+    let simulated = Simulated::new();
+    assert!(panic::catch_unwind(|| panics(&simulated)).is_err());
+    assert_eq!(simulated.startups(), 0);
+}
+
+#[wsa_startup::main(backend = simulated.clone(), version = "1.1", on_error = "return")]
+fn versioned(simulated: &Simulated) -> Result<(), WsaError> {
+    Ok(())
+}
+
+#[test]
+fn uses_version() {
+    let simulated = Simulated::supporting(WsaVersion::new(2, 0), WsaVersion::V2_2);
+    assert_eq!(versioned(&simulated), Err(WsaError::VersionNotSupported));
+
+    let simulated = Simulated::supporting(WsaVersion::new(1, 0), WsaVersion::V2_2);
+    assert_eq!(versioned(&simulated), Ok(()));
+    assert_eq!(simulated.startups(), 0);
+}
+
+#[wsa_startup::main(backend = simulated.clone(), on_error = "return")]
+fn returns_error(simulated: &Simulated) -> Result<(), Box<dyn std::error::Error>> {
+    unreachable!("WSA failed to initialize")
+}
+
+#[test]
+fn on_error_return() {
+    let simulated = Simulated::new();
+    simulated.fail_startup(WsaError::SystemNotReady);
+    let err = returns_error(&simulated).unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<WsaError>(),
+        Some(&WsaError::SystemNotReady)
+    );
+}
+
+#[wsa_startup::main(backend = simulated.clone())]
+fn fails(simulated: &Simulated) {
+    unreachable!("WSA failed to initialize")
+}
+
+#[test]
+fn on_error_panic() {
+    let simulated = Simulated::new();
+    simulated.fail_startup(WsaError::TasksLimitReached);
+    let panic = panic::catch_unwind(|| fails(&simulated)).unwrap_err();
+    let message = panic.downcast_ref::<String>().unwrap();
+    assert!(message.starts_with("failed to initialize WSA: "));
+}
+
+#[allow(dead_code)]
+#[wsa_startup::main(on_error = "exit", exit_code = 3)]
+fn exits() {}
+
+#[wsa_startup::main(backend = simulated.clone())]
+async fn startups_async(simulated: &Simulated) -> usize {
+    tokio::task::yield_now().await;
+    simulated.startups()
+}
+
+#[tokio::test]
+async fn async_functions() {
+    let simulated = Simulated::new();
+    assert_eq!(startups_async(&simulated).await, 1);
+    assert_eq!(simulated.startups(), 0);
+}
+
+#[wsa_startup::main(backend = simulated.clone())]
+#[tokio::main(flavor = "current_thread")]
+async fn above_runtime(simulated: &Simulated) -> usize {
+    simulated.startups()
+}
+
+#[tokio::main(flavor = "current_thread")]
+#[wsa_startup::main(backend = simulated.clone())]
+async fn below_runtime(simulated: &Simulated) -> usize {
+    simulated.startups()
+}
+
+#[test]
+fn with_runtime_attribute() {
+    let simulated = Simulated::new();
+    assert_eq!(above_runtime(&simulated), 1);
+    assert_eq!(below_runtime(&simulated), 1);
+    assert_eq!(simulated.startups(), 0);
+}
+
+#[wsa_startup::main]
+fn real_winsock() -> bool {
+    wsa_startup::last_error().is_none()
+}
+
+#[test]
+fn default_backend() {
+    assert!(real_winsock());
+}
@@ -0,0 +1,193 @@
+//! Checks the crate against every target it supports, with each choice of bindings.
+//!
+//! Other targets need their standard libraries installed with `rustup target add` first.
+//! Targets without one are skipped, unless `WSA_STARTUP_ALL_TARGETS` is set as CI does,
+//! in which case they fail the checks.
+
+use object::{read::archive::ArchiveFile, Object, ObjectSymbol};
+use std::{env, fs, path::Path, process::Command};
+
+const TARGETS: &[&str] = &[
+    "x86_64-unknown-linux-gnu",
+    "x86_64-pc-windows-msvc",
+    "i686-pc-windows-msvc",
+    "aarch64-pc-windows-msvc",
+    "x86_64-pc-windows-gnu",
+];
+
+/// The features selecting the bindings, each checked along with every feature in [`OPTIONAL`].
+/// With `dynamic` alone `ws2_32` is loaded at runtime, with the bindings it is only a backend next to them
+const BINDINGS: &[&str] = &[
+    "dynamic",
+    "winapi",
+    "windows-sys",
+    "winapi,windows-sys",
+    "dynamic,winapi",
+    "dynamic,windows-sys",
+    "dynamic,winapi,windows-sys",
+];
+
+/// Every other optional feature
+const OPTIONAL: &str = "macros,tracing,log,serde,capi";
+
+fn cargo() -> Command {
+    Command::new(env::var_os("CARGO").unwrap_or_else(|| "cargo".into()))
+}
+
+/// Set to fail the checks for targets whose standard library isn't installed, instead of skipping them.
+/// Install them all first with `rustup target add x86_64-pc-windows-msvc i686-pc-windows-msvc
+/// aarch64-pc-windows-msvc x86_64-pc-windows-gnu`
+const ALL_TARGETS: &str = "WSA_STARTUP_ALL_TARGETS";
+
+/// Whether the standard library for `target` is installed
+fn installed(target: &str) -> bool {
+    let output = Command::new(env::var_os("RUSTC").unwrap_or_else(|| "rustc".into()))
+        .args(["--print", "target-libdir", "--target", target])
+        .output()
+        .expect("rustc should be runnable");
+    let libdir = String::from_utf8_lossy(&output.stdout);
+    Path::new(libdir.trim()).read_dir().is_ok_and(|entries| {
+        entries
+            .flatten()
+            .any(|entry| entry.file_name().to_string_lossy().starts_with("libstd-"))
+    })
+}
+
+/// The `targets` whose standard library is installed, see [`ALL_TARGETS`]
+fn available<'a>(targets: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+    let (installed, missing): (Vec<_>, Vec<_>) =
+        targets.into_iter().partition(|target| installed(target));
+    if !missing.is_empty() {
+        assert!(
+            env::var_os(ALL_TARGETS).is_none(),
+            "the standard libraries for {:?} aren't installed",
+            missing
+        );
+        eprintln!(
+            "skipping {missing:?} as their standard libraries aren't installed, \
+             set {ALL_TARGETS} to fail instead"
+        );
+    }
+    installed
+}
+
+#[test]
+fn check_matrix() {
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("targets");
+    let targets = available(TARGETS.iter().copied());
+    // Checking only the host proves nothing about the bindings
+    if !targets.iter().any(|target| target.contains("windows")) {
+        eprintln!(
+            "skipping the matrix as no windows target is installed, \
+             set {ALL_TARGETS} to fail instead"
+        );
+        return;
+    }
+    let mut failed = Vec::new();
+    for target in targets {
+        for bindings in BINDINGS {
+            let features = format!("{OPTIONAL},{bindings}");
+            let status = cargo()
+                .current_dir(env!("CARGO_MANIFEST_DIR"))
+                .args(["check", "--all-targets", "--quiet", "--no-default-features"])
+                .args(["--features", &features])
+                .args(["--target", target])
+                .arg("--target-dir")
+                .arg(&target_dir)
+                .status()
+                .expect("cargo should be runnable");
+            if !status.success() {
+                failed.push(format!("{target} with {features}"));
+            }
+        }
+    }
+    assert!(failed.is_empty(), "checking failed for {:?}", failed);
+}
+
+/// Every Winsock function the crate calls
+const WINSOCK: &[&str] = &[
+    "WSAStartup",
+    "WSACleanup",
+    "WSAGetLastError",
+    "WSASetLastError",
+    "WSAEnumProtocolsW",
+];
+
+/// The Winsock functions the crate's own code imports, built for `target` with only `features`
+fn winsock_imports(target: &str, features: &str) -> Vec<String> {
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("imports")
+        .join(features);
+    let status = cargo()
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(["build", "--lib", "--quiet", "--no-default-features"])
+        .args(["--features", features])
+        .args(["--target", target])
+        .arg("--target-dir")
+        .arg(&target_dir)
+        .status()
+        .expect("cargo should be runnable");
+    assert!(status.success(), "building for {} failed", target);
+
+    let rlib = target_dir.join(target).join("debug/libwsa_startup.rlib");
+    let data = fs::read(rlib).unwrap();
+    let archive = ArchiveFile::parse(&*data).unwrap();
+    let mut imports = Vec::new();
+    for member in archive.members() {
+        let member = member.unwrap();
+        // Only the object files matter, not the metadata next to them
+        if !member.name().ends_with(b".o") {
+            continue;
+        }
+        // Copied out of the archive, as objects have to be aligned to be parsed
+        let bytes = member.data(&*data).unwrap().to_vec();
+        let object = object::File::parse(&*bytes).unwrap();
+        imports.extend(
+            object
+                .symbols()
+                .filter(ObjectSymbol::is_undefined)
+                .filter_map(|symbol| symbol.name().ok())
+                .map(|name| name.strip_prefix("__imp_").unwrap_or(name))
+                .filter(|name| WINSOCK.contains(name))
+                .map(str::to_owned),
+        );
+    }
+    imports.sort();
+    imports.dedup();
+    imports
+}
+
+#[test]
+fn dynamic_imports_no_winsock() {
+    const TARGET: &str = "x86_64-pc-windows-msvc";
+    if available([TARGET]).is_empty() {
+        return;
+    }
+    assert_eq!(winsock_imports(TARGET, "dynamic"), Vec::<String>::new());
+    // The same check finds the imports when ws2_32 is linked
+    assert!(winsock_imports(TARGET, "dynamic,winapi").contains(&"WSAStartup".to_owned()));
+}
+
+#[test]
+fn dynamic_depends_on_no_bindings() {
+    for target in TARGETS.iter().filter(|target| target.contains("windows")) {
+        let output = cargo()
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            .args(["tree", "--edges", "normal", "--prefix", "none"])
+            .args(["--no-default-features", "--features", "macros,dynamic"])
+            .args(["--target", target])
+            .output()
+            .expect("cargo should be runnable");
+        assert!(output.status.success(), "cargo tree failed for {}", target);
+        let tree = String::from_utf8_lossy(&output.stdout);
+        for bindings in ["winapi ", "windows-sys "] {
+            assert!(
+                !tree.lines().any(|line| line.starts_with(bindings)),
+                "{} is a dependency on {}",
+                bindings,
+                target
+            );
+        }
+        assert!(tree.lines().any(|line| line.starts_with("libloading ")));
+    }
+}
@@ -0,0 +1,80 @@
+//! Tests for the `#[wsa_startup::test]` attribute
+#![cfg(feature = "macros")]
+
+use std::panic;
+use wsa_startup::{Simulated, WsaError, WsaInitializer, WsaVersion};
+
+#[wsa_startup::test]
+fn initialized_with_winsock() {
+    let wsa = WsaInitializer::default().init().unwrap();
+    assert_eq!(wsa.try_clean(), Ok(()));
+}
+
+#[wsa_startup::test(simulated)]
+fn binds_simulated() {
+    assert_eq!(simulated.startups(), 1);
+}
+
+#[wsa_startup::test(simulated = winsock, version = "1.1")]
+fn binds_simulated_by_name() {
+    assert_eq!(winsock.startups(), 1);
+    let wsa = WsaInitializer::with_backend(winsock.clone())
+        .init()
+        .unwrap();
+    assert_eq!(wsa.info().version(), WsaVersion::V2_2);
+    wsa.clean();
+}
+
+#[wsa_startup::test(simulated, fail_startup = [SystemNotReady, WsaError::Other(42)])]
+fn injects_startup_failures() {
+    let init = || WsaInitializer::with_backend(simulated.clone()).init();
+    assert_eq!(init().err(), Some(WsaError::SystemNotReady));
+    assert_eq!(init().err(), Some(WsaError::Other(42)));
+    init().unwrap().clean();
+    assert_eq!(simulated.startups(), 1);
+}
+
+#[wsa_startup::test(simulated, fail_cleanup = [NetworkDown])]
+fn injects_cleanup_failures() {
+    let wsa = WsaInitializer::with_backend(simulated.clone())
+        .init()
+        .unwrap();
+    assert_eq!(wsa.try_clean(), Err(WsaError::NetworkDown));
+    assert_eq!(simulated.startups(), 2);
+}
+
+#[wsa_startup::test(simulated, on_error = "return")]
+fn returns_results() -> Result<(), WsaError> {
+    WsaInitializer::with_backend(simulated.clone())
+        .init()?
+        .try_clean()
+}
+
+thread_local! {
+    static SIMULATED: Simulated = Simulated::new();
+}
+
+#[wsa_startup::test(backend = SIMULATED.with(Clone::clone))]
+#[should_panic = "in the body"]
+fn panics() {
+    assert_eq!(SIMULATED.with(Simulated::startups), 1);
+    panic!("in the body");
+}
+
+#[test]
+fn cleaned_up_on_panic() {
+    assert!(panic::catch_unwind(panics).is_err());
+    assert_eq!(SIMULATED.with(Simulated::startups), 0);
+}
+
+#[wsa_startup::test(simulated)]
+#[tokio::test]
+async fn above_runtime() {
+    assert_eq!(simulated.startups(), 1);
+}
+
+#[tokio::test]
+#[wsa_startup::test(simulated)]
+async fn below_runtime() {
+    assert_eq!(simulated.startups(), 1);
+}
@@ -0,0 +1,31 @@
+//! Compile-fail tests for [`WsaToken`](wsa_startup::WsaToken)
+//!
+//! The `.stderr` snapshots are the diagnostics of the pinned [`TOOLCHAIN`], as their wording changes
+//! between compiler versions, from the `rust-version` up. On any other toolchain the test is skipped,
+//! run it with `cargo +1.95 test --test token`, and regenerate the snapshots with `TRYBUILD=overwrite`
+//! when moving the pin.
+
+use std::{env, process::Command};
+
+/// The version of the compiler whose diagnostics the snapshots hold
+const TOOLCHAIN: &str = "1.95";
+
+/// Whether the compiler building the tests is the pinned [`TOOLCHAIN`]
+fn pinned() -> bool {
+    let output = Command::new(env::var_os("RUSTC").unwrap_or_else(|| "rustc".into()))
+        .arg("--version")
+        .output()
+        .expect("rustc should be runnable");
+    let version = String::from_utf8_lossy(&output.stdout);
+    version.starts_with(&format!("rustc {TOOLCHAIN}."))
+}
+
+#[test]
+fn token_guarantees() {
+    if !pinned() {
+        eprintln!("skipping the compile-fail tests as their snapshots are of rustc {TOOLCHAIN}");
+        return;
+    }
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,10 @@
+use wsa_startup::{WsaInitializer, WsaToken};
+
+fn token() -> WsaToken<'static> {
+    let raii = WsaInitializer::default().init().unwrap().raii();
+    raii.token()
+}
+
+fn main() {
+    token();
+}
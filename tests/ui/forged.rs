@@ -0,0 +1,6 @@
+use std::marker::PhantomData;
+use wsa_startup::WsaToken;
+
+fn main() {
+    let _token: WsaToken<'static> = WsaToken(PhantomData);
+}
@@ -0,0 +1,8 @@
+use wsa_startup::WsaInitializer;
+
+fn main() {
+    let wsa = WsaInitializer::default().init().unwrap();
+    let token = wsa.token();
+    wsa.clean();
+    drop(token);
+}
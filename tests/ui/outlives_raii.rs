@@ -0,0 +1,12 @@
+use wsa_startup::{WsaInitializer, WsaToken};
+
+struct Socket<'wsa> {
+    _wsa: WsaToken<'wsa>,
+}
+
+fn main() {
+    let raii = WsaInitializer::default().init().unwrap().raii();
+    let socket = Socket { _wsa: raii.token() };
+    drop(raii);
+    drop(socket);
+}